@@ -32,7 +32,8 @@ function build_circuit() {
     fi
 
     # 使用circom编译.circom文件,生成r1cs文件
-    circom "$input_file" --O2 -l ./node_modules --r1cs --wasm --output "$output_dir" 
+    # 其余参数原样传给circom,例如 --prime bls12381
+    circom "$input_file" --O2 -l ./node_modules --r1cs --wasm --output "$output_dir" "${@:3}"
 
     if [ $? -eq 0 ]; then
         echo "r1cs文件生成成功,输出目录: $output_dir"
@@ -65,8 +66,36 @@ if [[ $1 == "custom" ]]; then
 
     cp -r circuits/* $temp_dir
     cp customized/$2.circom $temp_dir/custom.circom
-    mv $temp_dir/check_vc.circom $temp_dir/$2.circom 
+    # 定制文件中定义的模板覆盖 custom_defaults.circom 中的空实现
+    for name in $(grep -oP '^template \K\w+' $temp_dir/custom.circom); do
+        awk -v name="$name" '
+            skip { if (/^}/) skip = 0; next }
+            /^\/\// { comment = comment $0 "\n"; next }
+            $0 ~ "^template " name "\\(" { skip = 1; comment = ""; next }
+            { printf "%s", comment; comment = ""; print }
+        ' $temp_dir/custom_defaults.circom > $temp_dir/custom_defaults.tmp
+        mv $temp_dir/custom_defaults.tmp $temp_dir/custom_defaults.circom
+    done
+    # 第三个参数可选择主电路,例如 check_multi_vc
+    mv $temp_dir/${3:-check_vc}.circom $temp_dir/$2.circom 
     build_circuit "$temp_dir/$2.circom" output
+elif [[ $1 == "poseidon" ]]; then
+    build_circuit "./circuits/check_vc_poseidon.circom" output
+elif [[ $1 == "gcm" ]]; then
+    build_circuit "./circuits/check_vc_gcm.circom" output
+elif [[ $1 == "long" ]]; then
+    build_circuit "./circuits/check_vc_long.circom" output
+elif [[ $1 == "multi" ]]; then
+    build_circuit "./circuits/check_multi_vc.circom" output
+elif [[ $1 == "validity" ]]; then
+    build_circuit "./circuits/check_vc_validity.circom" output
+elif [[ $1 == "holder" ]]; then
+    build_circuit "./circuits/check_vc_holder.circom" output
+elif [[ $1 == "challenge" ]]; then
+    build_circuit "./circuits/check_vc_challenge.circom" output
+elif [[ $1 == "test_aes" ]]; then
+    build_circuit "./circuits/test_aes.circom" output
+    build_circuit "./circuits/test_aes.circom" output/bls12381 --prime bls12381
 else
     build_circuit "./circuits/check_vc.circom" output
 fi
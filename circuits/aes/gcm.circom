@@ -0,0 +1,196 @@
+// AES-GCM encryption without associated data, on top of the CTR circuit.
+// Bit order follows NIST SP 800-38D: bit 0 of a block is the most significant
+// bit of its first byte and the coefficient of x^0 in GF(2^128).
+
+pragma circom 2.1.9;
+
+include "ctr.circom";
+include "cipher.circom";
+include "circomlib/circuits/bitify.circom";
+
+template BytesToGcmBits(nBytes) {
+        signal input bytes[nBytes];
+        signal output bits[nBytes * 8];
+
+        component toBits[nBytes];
+        for (var i = 0; i < nBytes; i++) {
+                toBits[i] = Num2Bits(8);
+                toBits[i].in <== bytes[i];
+                for (var j = 0; j < 8; j++) {
+                        bits[i * 8 + j] <== toBits[i].out[7 - j];
+                }
+        }
+}
+
+template GcmBitsToBytes(nBytes) {
+        signal input bits[nBytes * 8];
+        signal output bytes[nBytes];
+
+        for (var i = 0; i < nBytes; i++) {
+                var lc = 0;
+                for (var j = 0; j < 8; j++) {
+                        lc += bits[i * 8 + j] * (1 << (7 - j));
+                }
+                bytes[i] <== lc;
+        }
+}
+
+template XorBits128() {
+        signal input a[128];
+        signal input b[128];
+        signal output out[128];
+
+        for (var i = 0; i < 128; i++) {
+                out[i] <== a[i] + b[i] - 2 * a[i] * b[i];
+        }
+}
+
+// Multiplication in GF(2^128) modulo x^128 + x^7 + x^2 + x + 1.
+// Every coefficient of the product is the parity of a sum of partial products,
+// at most 128 * 128 < 2^15 of them. About 18K constraints: the 128 * 128
+// partial products and a Num2Bits(15) per coefficient.
+template GfMul() {
+        signal input a[128];
+        signal input b[128];
+        signal output out[128];
+
+        // rep[k] is x^k reduced modulo the field polynomial.
+        var rep[255][128];
+        for (var k = 0; k < 128; k++) {
+                rep[k][k] = 1;
+        }
+        for (var k = 128; k < 255; k++) {
+                var overflow = rep[k - 1][127];
+                rep[k][0] = overflow;
+                for (var m = 1; m < 128; m++) {
+                        rep[k][m] = rep[k - 1][m - 1];
+                }
+                rep[k][1] = (rep[k][1] + overflow) % 2;
+                rep[k][2] = (rep[k][2] + overflow) % 2;
+                rep[k][7] = (rep[k][7] + overflow) % 2;
+        }
+
+        signal prod[128][128];
+        for (var i = 0; i < 128; i++) {
+                for (var j = 0; j < 128; j++) {
+                        prod[i][j] <== a[i] * b[j];
+                }
+        }
+
+        component parity[128];
+        for (var m = 0; m < 128; m++) {
+                var lc = 0;
+                for (var i = 0; i < 128; i++) {
+                        for (var j = 0; j < 128; j++) {
+                                if (rep[i + j][m] == 1) {
+                                        lc += prod[i][j];
+                                }
+                        }
+                }
+                parity[m] = Num2Bits(15);
+                parity[m].in <== lc;
+                out[m] <== parity[m].out[0];
+        }
+}
+
+// GHASH of nBlocks blocks under the hash key h, one GfMul per block.
+template GHash(nBlocks) {
+        signal input blocks[nBlocks][128];
+        signal input h[128];
+        signal output out[128];
+
+        signal acc[nBlocks + 1][128];
+        component mix[nBlocks];
+        component mul[nBlocks];
+
+        for (var i = 0; i < 128; i++) {
+                acc[0][i] <== 0;
+        }
+        for (var n = 0; n < nBlocks; n++) {
+                mix[n] = XorBits128();
+                mix[n].a <== acc[n];
+                mix[n].b <== blocks[n];
+
+                mul[n] = GfMul();
+                mul[n].a <== mix[n].out;
+                mul[n].b <== h;
+                acc[n + 1] <== mul[n].out;
+        }
+        out <== acc[nBlocks];
+}
+
+template EncryptBlock(nk) {
+        signal input in[16];
+        signal input key[nk * 4];
+        signal output out[16];
+
+        component aes = Cipher(nk);
+        aes.key <== key;
+        aes.block <== ToBlocks(16)(in).blocks[0];
+
+        component toStream = ToStream(1, 16);
+        toStream.blocks[0] <== aes.cipher;
+        out <== toStream.stream;
+}
+
+// The nonce is iv[0..12]; iv[12..16] must be zero. The payload is encrypted
+// from counter 2 and the tag is masked with the keystream of counter 1.
+template EncryptGCM(l, nk) {
+        signal input plainText[l];
+        signal input iv[16];
+        signal input key[nk * 4];
+        signal output cipher[l];
+        signal output tag[16];
+
+        for (var i = 12; i < 16; i++) {
+                iv[i] === 0;
+        }
+
+        signal j0[16];
+        signal ctrIV[16];
+        for (var i = 0; i < 15; i++) {
+                j0[i] <== iv[i];
+                ctrIV[i] <== iv[i];
+        }
+        j0[15] <== 1;
+        ctrIV[15] <== 2;
+
+        cipher <== EncryptCTR(l, nk)(plainText, ctrIV, key);
+
+        signal zero[16];
+        for (var i = 0; i < 16; i++) {
+                zero[i] <== 0;
+        }
+        signal hBits[128] <== BytesToGcmBits(16)(EncryptBlock(nk)(zero, key));
+        signal maskBits[128] <== BytesToGcmBits(16)(EncryptBlock(nk)(j0, key));
+
+        // Ciphertext blocks, zero padded, then the length block len(A) || len(C)
+        // in bits, both as 64-bit big endian.
+        var n = l \ 16;
+        if (l % 16 > 0) {
+                n = n + 1;
+        }
+        signal padded[n * 16 + 16];
+        for (var i = 0; i < l; i++) {
+                padded[i] <== cipher[i];
+        }
+        for (var i = l; i < n * 16 + 16; i++) {
+                var bitLen = l * 8;
+                var byte = 0;
+                if (i >= n * 16 + 8) {
+                        byte = (bitLen >> (8 * (n * 16 + 15 - i))) & 0xff;
+                }
+                padded[i] <== byte;
+        }
+
+        signal paddedBits[(n + 1) * 128] <== BytesToGcmBits(n * 16 + 16)(padded);
+        component ghash = GHash(n + 1);
+        for (var b = 0; b < n + 1; b++) {
+                for (var i = 0; i < 128; i++) {
+                        ghash.blocks[b][i] <== paddedBits[b * 128 + i];
+                }
+        }
+        ghash.h <== hBits;
+
+        tag <== GcmBitsToBytes(16)(XorBits128()(ghash.out, maskBits));
+}
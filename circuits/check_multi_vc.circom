@@ -0,0 +1,6 @@
+pragma circom 2.0.0;
+
+include "./vc.circom";
+
+// Two VCs in one proof, see `MultiVcProveInput`.
+component main {public [extensions]} = VerifyMultiVC(2, 40, 0, 0, 1, 0, 0, 0);
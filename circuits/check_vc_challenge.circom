@@ -0,0 +1,6 @@
+pragma circom 2.0.0;
+
+include "./vc.circom";
+
+// Proofs bound to a verifier's challenge, see `PresentationRequest`.
+component main {public [extensions, challenge]} = VerifyVC(40, 0, 0, 1, 0, 0, 1);
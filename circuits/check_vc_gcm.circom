@@ -0,0 +1,6 @@
+pragma circom 2.0.0;
+
+include "./vc.circom";
+
+// AES-256-GCM storage leaf, see `LeafCipher::Aes256Gcm`.
+component main {public [extensions]} = VerifyVC(40, 0, 3, 1, 0, 0, 0);
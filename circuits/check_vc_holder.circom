@@ -0,0 +1,6 @@
+pragma circom 2.0.0;
+
+include "./vc.circom";
+
+// VCs bound to a holder key, see `VC::with_holder`.
+component main {public [extensions]} = VerifyVC(40, 0, 0, 1, 0, 1, 0);
@@ -0,0 +1,6 @@
+pragma circom 2.0.0;
+
+include "./vc.circom";
+
+// VCs with extra attributes, spanning two leaves, see `VC::chunks`.
+component main {public [extensions]} = VerifyVC(40, 0, 0, 2, 0, 0, 0);
@@ -0,0 +1,6 @@
+pragma circom 2.0.0;
+
+include "./vc.circom";
+
+// Poseidon leaf commitment and merkle tree, see `HashMode::Poseidon`.
+component main {public [extensions]} = VerifyVC(40, 1, 0, 1, 0, 0, 0);
@@ -0,0 +1,6 @@
+pragma circom 2.0.0;
+
+include "./vc.circom";
+
+// VCs with an issuance or expiry date, checked against the public `now`.
+component main {public [extensions, now]} = VerifyVC(40, 0, 0, 1, 1, 0, 0);
@@ -14,4 +14,4 @@ template CustomCheck() {
     signal input extensions[num_extensions];
 
     // 在这里放置自定义逻辑
-}
\ No newline at end of file
+}
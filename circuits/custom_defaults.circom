@@ -0,0 +1,29 @@
+pragma circom 2.0.0;
+
+// Default custom checks that do nothing. `./build_circuit.sh custom` drops the
+// templates the customization defines itself, so it overrides only what it needs.
+
+// Custom logic over the extra attributes of a VC spanning several leaves:
+// a 2-byte little endian length, the attributes and zero padding.
+template CustomCheckExtra(extra_len) {
+    var num_extensions = 16;
+
+    signal input extra[extra_len];
+
+    signal input extensions[num_extensions];
+}
+
+// Custom logic relating the fields of the VCs of `VerifyMultiVC`.
+template CustomMultiCheck(n) {
+    var name_len = 16;
+    var serial_len = 32;
+    var num_extensions = 16;
+
+    signal input name[n][name_len];
+    signal input age[n];
+    signal input eduLevel[n];
+    signal input serialNo[n][serial_len];
+    signal input birthDateInt[n];
+
+    signal input extensions[num_extensions];
+}
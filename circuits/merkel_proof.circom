@@ -1,6 +1,7 @@
 pragma circom 2.0.0;
 
 include "./keccak/keccak.circom";
+include "./poseidon.circom";
 include "./utils.circom";
 
 // Import from @circomlib/circuits
@@ -21,6 +22,47 @@ template HashLeftRight() {
     hash <== PackHash()(hashOutput);
 }
 
+template HashNode(poseidon) {
+    signal input left[2];
+    signal input right[2];
+    signal output hash[2];
+
+    if (poseidon == 1) {
+        hash <== HashLeftRightPoseidon()(left, right);
+    } else {
+        hash <== HashLeftRight()(left, right);
+    }
+}
+
+template HashLeaf(poseidon) {
+    signal input leaf[256];
+    signal output hash[2];
+
+    if (poseidon == 1) {
+        hash <== HashLeafPoseidon()(leaf);
+    } else {
+        hash <== PackHash()(Keccak(256 * 8, 256)(BytesToBits(256)(leaf)));
+    }
+}
+
+// The root of the subtree over `chunks` consecutive leaves, a power of two.
+// nodes[chunks - 1 + i] is leaf i and nodes[i] hashes nodes[2i + 1] and nodes[2i + 2].
+template HashChunks(poseidon, chunks) {
+    assert(chunks > 0 && (chunks & (chunks - 1)) == 0);
+    signal input data[chunks * 256];
+    signal output hash[2];
+
+    signal nodes[2 * chunks - 1][2];
+    for (var i = 0; i < chunks; i++) {
+        nodes[chunks - 1 + i] <== HashLeaf(poseidon)(ArraySlice(chunks * 256, i * 256, 256)(data));
+    }
+    for (var i = chunks - 2; i >= 0; i--) {
+        nodes[i] <== HashNode(poseidon)(nodes[2 * i + 1], nodes[2 * i + 2]);
+    }
+
+    hash <== nodes[0];
+}
+
 // if s == 0 returns [in[0], in[1]]
 // if s == 1 returns [in[1], in[0]]
 template DualMux() {
@@ -38,7 +80,7 @@ template DualMux() {
 
 // Verifies that merkle proof is correct for given merkle root and a leaf
 // pathIndices input is an array of 0/1 selectors telling whether given pathElement is on the left or right side of merkle path
-template MerkleTreeChecker(levels) {
+template MerkleTreeChecker(levels, poseidon) {
     signal input leafHash[2];
     signal input pathElements[levels][2];
     signal input pathIndices[levels];
@@ -56,7 +98,7 @@ template MerkleTreeChecker(levels) {
         selectors[i].in[1] <== pathElements[i];
         selectors[i].s <== pathIndices[i];
 
-        levelOutput[i] <== HashLeftRight()(selectors[i].out[0], selectors[i].out[1]);
+        levelOutput[i] <== HashNode(poseidon)(selectors[i].out[0], selectors[i].out[1]);
     }
 
     root <== SelectArrayElement(levels)(pathLength - 1, levelOutput);
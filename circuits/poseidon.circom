@@ -0,0 +1,61 @@
+pragma circom 2.0.0;
+
+include "./utils.circom";
+
+// Import from @circomlib/circuits
+include "circomlib/circuits/bitify.circom";
+include "circomlib/circuits/poseidon.circom";
+
+// A hash is a field element, split into two 128-bit halves like the packed
+// Keccak hashes so both modes share the same signals.
+template SplitHash() {
+    signal input in;
+    signal output out[2];
+
+    signal bits[254] <== Num2Bits_strict()(in);
+    out[0] <== Bits2Num(128)(ArraySlice(254, 0, 128)(bits));
+    out[1] <== Bits2Num(126)(ArraySlice(254, 128, 126)(bits));
+}
+
+// Little endian packing of at most 31 bytes, which must be range checked.
+template PackBytes(n) {
+    assert(n <= 31);
+    signal input in[n];
+    signal output out;
+
+    var lc = 0;
+    for (var i = 0; i < n; i++) {
+        lc += in[i] * (1 << (i * 8));
+    }
+    out <== lc;
+}
+
+// The leaf bytes come out of the AES circuit and are already range checked.
+template HashLeafPoseidon() {
+    var leaf_len = 256;
+    var chunk_len = 31;
+    var num_chunks = (leaf_len + chunk_len - 1) \ chunk_len;
+
+    signal input leaf[leaf_len];
+    signal output hash[2];
+
+    signal chunks[num_chunks];
+    for (var i = 0; i < num_chunks; i++) {
+        var len = i == num_chunks - 1 ? leaf_len - i * chunk_len : chunk_len;
+        chunks[i] <== PackBytes(len)(ArraySlice(leaf_len, i * chunk_len, len)(leaf));
+    }
+
+    hash <== SplitHash()(Poseidon(num_chunks)(chunks));
+}
+
+template HashLeftRightPoseidon() {
+    signal input left[2];
+    signal input right[2];
+    signal output hash[2];
+
+    signal packed[2];
+    packed[0] <== left[0] + left[1] * (1 << 128);
+    packed[1] <== right[0] + right[1] * (1 << 128);
+
+    hash <== SplitHash()(Poseidon(2)(packed));
+}
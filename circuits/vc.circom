@@ -0,0 +1,325 @@
+pragma circom 2.0.0;
+
+// Import from @circomlib/circuits
+include "circomlib/circuits/comparators.circom";
+include "circomlib/circuits/bitify.circom";
+
+include "./keccak/keccak.circom";
+include "./merkel_proof.circom";
+include "./utils.circom";
+include "./custom.circom";
+include "./custom_defaults.circom";
+include "./aes/ctr.circom";
+include "./aes/gcm.circom";
+include "./poseidon.circom";
+include "circomlib/circuits/babyjub.circom";
+
+function VcLen() {
+    return 79;
+}
+
+// The leaf cipher, see `LeafCipher`: 0 AES-128-CTR, 1 AES-256-CTR,
+// 2 AES-128-GCM, 3 AES-256-GCM.
+function AesNk(cipher) {
+    return cipher % 2 == 1 ? 8 : 4;
+}
+
+// validity: 1 if the VC has an issuance or expiry date, see `VALIDITY_LEN`.
+function ValidityLen(validity) {
+    return validity == 1 ? 6 + 8 + 7 + 8 : 0;
+}
+
+// The extra section of a VC spanning `chunks` leaves, see `extra_len`: a
+// 2-byte little endian length, the extra attributes and zero padding. The leaf
+// also holds the IV, the VC hash, the cipher id and the GCM tag.
+// holder: 1 if the VC is bound to a holder, see `HOLDER_LEN`.
+function HolderLen(holder) {
+    return holder == 1 ? 6 + 32 + 32 : 0;
+}
+
+// The fixed fields and the optional validity and holder sections.
+function FixedLen(validity, holder) {
+    return VcLen() + ValidityLen(validity) + HolderLen(holder);
+}
+
+function ExtraLen(chunks, fixed_len) {
+    return chunks == 1 ? 0 : chunks * 256 - fixed_len - (16 + 32 + 1 + 16);
+}
+
+function EncodedLen(chunks, fixed_len) {
+    return fixed_len + ExtraLen(chunks, fixed_len);
+}
+
+// The validity section holds "issued" and "expires", each followed by an
+// 8-byte little endian timestamp.
+template CheckValidity() {
+    signal input validity[6 + 8 + 7 + 8];
+    signal input now;
+
+    signal issuedAt <== BytesToInt()(ArraySlice(29, 6, 8)(validity));
+    signal expiresAt <== BytesToInt()(ArraySlice(29, 21, 8)(validity));
+
+    component nowBits = Num2Bits(64);
+    nowBits.in <== now;
+
+    signal issued <== LessEqThan(64)([issuedAt, now]);
+    issued === 1;
+
+    signal noExpiry <== IsZero()(expiresAt);
+    signal beforeExpiry <== LessThan(64)([now, expiresAt]);
+    (1 - noExpiry) * (1 - beforeExpiry) === 0;
+}
+
+// The holder section holds "holder" and the little endian coordinates of the
+// holder public key, which must be derived from `secret`.
+template CheckHolder() {
+    signal input holder[6 + 32 + 32];
+    signal input secret;
+
+    var x = 0;
+    var y = 0;
+    for (var i = 0; i < 32; i++) {
+        x += holder[6 + i] * (1 << (i * 8));
+        y += holder[38 + i] * (1 << (i * 8));
+    }
+
+    component pbk = BabyPbk();
+    pbk.in <== secret;
+    pbk.Ax === x;
+    pbk.Ay === y;
+}
+
+template DecodeVC() {
+    var input_len = VcLen(); 
+    var name_prefix_len = 4;
+    var name_len = 16;
+    var age_prefix_len = 3;
+    var age_len = 1;
+    var birth_prefix_len = 5;
+    var birth_len = 8;
+    var edu_prefix_len = 3;
+    var edu_len = 1;
+    var serial_prefix_len = 6;
+    var serial_len = 32;
+
+    signal input encoded[input_len];
+
+    signal birthDate[birth_len];
+
+    signal output name[name_len];
+    signal output age;
+    signal output eduLevel;
+    signal output serialNo[serial_len];
+
+    signal output birthDateInt;
+
+    // 将输入信号分割为各个字段
+    var name_offset = name_prefix_len;
+    name <== ArraySlice(input_len, name_offset, name_len)(encoded);
+
+    var age_offset = name_offset + name_len + age_prefix_len;
+    age <== encoded[age_offset];
+
+    var birth_offset = age_offset + age_len + birth_prefix_len;
+    birthDate <== ArraySlice(input_len, birth_offset, birth_len)(encoded);
+
+    var edu_offset = birth_offset + birth_len + edu_prefix_len;
+    eduLevel <== encoded[edu_offset];
+
+    var serial_offset = edu_offset + edu_len + serial_prefix_len;
+    serialNo <== ArraySlice(input_len, serial_offset, serial_len)(encoded);
+
+    birthDateInt <== BytesToInt()(birthDate);
+}
+
+// poseidon: 0 commits to the leaf with Keccak, 1 with Poseidon.
+// cipher: the leaf cipher, recorded in the byte after the ciphertext and
+// followed by the GCM tag, zero in counter mode.
+// chunks: the number of 256-byte leaves the VC spans, a power of two. The
+// hash is the root of their subtree.
+template HashVC(poseidon, cipher, chunks, fixed_len) {
+    var input_len = EncodedLen(chunks, fixed_len);
+    var hash_len = 32;
+    var leaf_len = 256 * chunks;
+    var tag_len = 16;
+    var encrypt_len = input_len + hash_len;
+    var cipher_offset = 16 + encrypt_len;
+    var tag_offset = cipher_offset + 1;
+    assert(tag_offset + tag_len <= leaf_len);
+    var nk = AesNk(cipher);
+
+    signal input encoded[input_len];
+    signal input aesKey[nk * 4];
+    signal input aesIV[16];
+    signal output leafHash[2];
+    
+    component vcHasher = Keccak(input_len * 8, hash_len * 8);
+    vcHasher.in <== BytesToBits(input_len)(encoded);
+    signal vcHashBytes[hash_len] <== BitsToBytes(hash_len)(vcHasher.out);
+
+    signal plainText[encrypt_len] <== ConcatArray(input_len, hash_len)(encoded, vcHashBytes);
+    signal cipherText[encrypt_len];
+    signal tag[tag_len];
+    if (cipher >= 2) {
+        (cipherText, tag) <== EncryptGCM(encrypt_len, nk)(plainText, aesIV, aesKey);
+    } else {
+        cipherText <== EncryptCTR(encrypt_len, nk)(plainText, aesIV, aesKey);
+        for (var i = 0; i < tag_len; i++) {
+            tag[i] <== 0;
+        }
+    }
+
+    signal paddedLeaf[leaf_len];
+    for (var i = 0; i < leaf_len; i++) {
+        if (i < 16) {
+            paddedLeaf[i] <== aesIV[i];
+        } else if (i < cipher_offset) {
+            paddedLeaf[i] <== cipherText[i - 16];
+        } else if (i == cipher_offset) {
+            paddedLeaf[i] <== cipher;
+        } else if (i < tag_offset + tag_len) {
+            paddedLeaf[i] <== tag[i - tag_offset];
+        } else {
+            paddedLeaf[i] <== 0;
+        }
+    }
+
+    leafHash <== HashChunks(poseidon, chunks)(paddedLeaf);
+}
+
+// A public input that appears in no constraint could be changed without
+// invalidating the proof on backends that rely on circom's own binding, such
+// as snarkjs, so the challenge is tied in with a square. ark-groth16 already
+// adds a constraint per instance variable and does not need it.
+template BindChallenge() {
+    signal input challenge[2];
+
+    signal square[2];
+    for (var i = 0; i < 2; i++) {
+        square[i] <== challenge[i] * challenge[i];
+    }
+}
+
+// validity: 1 checks `issued_at <= now < expires_at` against the public
+// current date `now`; an unset expiry date is zero and never expires.
+// holder: 1 checks the private `holderSecret` against the holder public key.
+// challenged: 1 takes a public `challenge` from the verifier, the two halves
+// of a keccak hash, so a proof cannot be replayed to another verifier session.
+template VerifyVC(levels, poseidon, cipher, chunks, validity, holder, challenged) {
+    // 参数：
+    var fixed_len = FixedLen(validity, holder);
+    var vc_len = EncodedLen(chunks, fixed_len);
+    var holder_offset = VcLen() + ValidityLen(validity);
+    var num_extensions = 16;
+
+    // 输入信号
+    signal input encodedVC[vc_len];
+    signal input aesKey[AesNk(cipher) * 4];
+    signal input aesIV[16];
+    signal input extensions[num_extensions];
+    signal input pathElements[levels][2];
+    signal input pathIndex;
+    signal input pathLength;
+
+    // 输出信号
+    signal output root[2];
+
+    component decodeVC = DecodeVC();
+    decodeVC.encoded <== ArraySlice(vc_len, 0, VcLen())(encodedVC);
+    
+    CustomCheck()(decodeVC.name, decodeVC.age, decodeVC.eduLevel, decodeVC.serialNo, decodeVC.birthDateInt, extensions);
+    if (chunks > 1) {
+        CustomCheckExtra(ExtraLen(chunks, fixed_len))(ArraySlice(vc_len, fixed_len, ExtraLen(chunks, fixed_len))(encodedVC), extensions);
+    }
+    if (validity == 1) {
+        signal input now;
+        CheckValidity()(ArraySlice(vc_len, VcLen(), ValidityLen(validity))(encodedVC), now);
+    }
+    if (holder == 1) {
+        signal input holderSecret;
+        CheckHolder()(ArraySlice(vc_len, holder_offset, HolderLen(holder))(encodedVC), holderSecret);
+    }
+    if (challenged == 1) {
+        signal input challenge[2];
+        BindChallenge()(challenge);
+    }
+    
+    // merkel proof
+    signal pathIndices[levels] <== Num2Bits(levels)(pathIndex);
+    signal leafHash[2] <== HashVC(poseidon, cipher, chunks, fixed_len)(encodedVC, aesKey, aesIV);
+    root <== MerkleTreeChecker(levels, poseidon)(leafHash, pathElements, pathIndices, pathLength);
+}
+
+// Proves n VCs in one statement, each with its own merkle proof and root, for
+// custom logic relating their fields, e.g. the same name on an ID and a diploma.
+// Every VC passes `CustomCheck`, as in `VerifyVC`, then all of them together
+// pass `CustomMultiCheck`.
+// The public signals are the n roots, in order, then the extensions and, with
+// validity checks, the current date and, when challenged, the challenge.
+template VerifyMultiVC(n, levels, poseidon, cipher, chunks, validity, holder, challenged) {
+    var fixed_len = FixedLen(validity, holder);
+    var vc_len = EncodedLen(chunks, fixed_len);
+    var holder_offset = VcLen() + ValidityLen(validity);
+    var name_len = 16;
+    var serial_len = 32;
+    var num_extensions = 16;
+
+    signal input encodedVC[n][vc_len];
+    signal input aesKey[n][AesNk(cipher) * 4];
+    signal input aesIV[n][16];
+    signal input extensions[num_extensions];
+    signal input pathElements[n][levels][2];
+    signal input pathIndex[n];
+    signal input pathLength[n];
+
+    signal output roots[n][2];
+
+    signal name[n][name_len];
+    signal age[n];
+    signal eduLevel[n];
+    signal serialNo[n][serial_len];
+    signal birthDateInt[n];
+    signal pathIndices[n][levels];
+    signal leafHash[n][2];
+
+    component decodeVC[n];
+    for (var i = 0; i < n; i++) {
+        decodeVC[i] = DecodeVC();
+        decodeVC[i].encoded <== ArraySlice(vc_len, 0, VcLen())(encodedVC[i]);
+        name[i] <== decodeVC[i].name;
+        age[i] <== decodeVC[i].age;
+        eduLevel[i] <== decodeVC[i].eduLevel;
+        serialNo[i] <== decodeVC[i].serialNo;
+        birthDateInt[i] <== decodeVC[i].birthDateInt;
+
+        CustomCheck()(name[i], age[i], eduLevel[i], serialNo[i], birthDateInt[i], extensions);
+        if (chunks > 1) {
+            CustomCheckExtra(ExtraLen(chunks, fixed_len))(ArraySlice(vc_len, fixed_len, ExtraLen(chunks, fixed_len))(encodedVC[i]), extensions);
+        }
+
+        pathIndices[i] <== Num2Bits(levels)(pathIndex[i]);
+        leafHash[i] <== HashVC(poseidon, cipher, chunks, fixed_len)(encodedVC[i], aesKey[i], aesIV[i]);
+        roots[i] <== MerkleTreeChecker(levels, poseidon)(leafHash[i], pathElements[i], pathIndices[i], pathLength[i]);
+    }
+
+    if (validity == 1) {
+        signal input now;
+        for (var i = 0; i < n; i++) {
+            CheckValidity()(ArraySlice(vc_len, VcLen(), ValidityLen(validity))(encodedVC[i]), now);
+        }
+    }
+
+    if (holder == 1) {
+        signal input holderSecret[n];
+        for (var i = 0; i < n; i++) {
+            CheckHolder()(ArraySlice(vc_len, holder_offset, HolderLen(holder))(encodedVC[i]), holderSecret[i]);
+        }
+    }
+
+    if (challenged == 1) {
+        signal input challenge[2];
+        BindChallenge()(challenge);
+    }
+
+    CustomMultiCheck(n)(name, age, eduLevel, serialNo, birthDateInt, extensions);
+}
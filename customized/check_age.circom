@@ -16,4 +16,4 @@ template CustomCheck() {
     // Check birthday threshold
     signal birthdayOutput <== LessThan(64)([birthDateInt, extensions[0]]);
     birthdayOutput === 1;
-}
\ No newline at end of file
+}
@@ -0,0 +1,44 @@
+pragma circom 2.0.0;
+
+// For `check_multi_vc` only. `CustomCheck` is empty and the birth date
+// threshold is checked in `CustomMultiCheck`, so building a single-VC circuit
+// with this file drops the threshold check.
+
+template CustomCheck() {
+    var name_len = 16;
+    var serial_len = 32;
+    var num_extensions = 16;
+
+    signal input name[name_len];
+    signal input age;
+    signal input eduLevel;
+    signal input serialNo[serial_len];
+    signal input birthDateInt;
+
+    signal input extensions[num_extensions];
+}
+
+// Every VC is issued to the same name and birth date, born before the threshold.
+template CustomMultiCheck(n) {
+    var name_len = 16;
+    var serial_len = 32;
+    var num_extensions = 16;
+
+    signal input name[n][name_len];
+    signal input age[n];
+    signal input eduLevel[n];
+    signal input serialNo[n][serial_len];
+    signal input birthDateInt[n];
+
+    signal input extensions[num_extensions];
+
+    for (var i = 1; i < n; i++) {
+        for (var j = 0; j < name_len; j++) {
+            name[i][j] === name[0][j];
+        }
+        birthDateInt[i] === birthDateInt[0];
+    }
+
+    signal birthdayOutput <== LessThan(64)([birthDateInt[0], extensions[0]]);
+    birthdayOutput === 1;
+}
@@ -0,0 +1,92 @@
+#ifndef VC_PROVE_H
+#define VC_PROVE_H
+
+/* Generated with cbindgen from src/ffi.rs, do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * A loaded proving key and circuit. Not thread-safe: use one handle per thread.
+ */
+typedef struct VcProver VcProver;
+
+/**
+ * A byte buffer allocated by this library.
+ */
+typedef struct VcBuffer {
+  uint8_t *data;
+  size_t len;
+} VcBuffer;
+
+/**
+ * Message of the last failed call on this thread, or null. The pointer stays
+ * valid until the next call into this library on the same thread.
+ */
+const char *vc_last_error(void);
+
+/**
+ * # Safety
+ * `buffer` must come from this library and be freed only once.
+ */
+void vc_buffer_free(struct VcBuffer buffer);
+
+/**
+ * Load `{dir}/{name}.pk` and the circuit files next to it. Returns null on failure.
+ *
+ * # Safety
+ * `dir` and `name` must be NUL-terminated strings.
+ */
+struct VcProver *vc_prover_load(const char *dir, const char *name);
+
+/**
+ * # Safety
+ * `prover` must come from [`vc_prover_load`] and be freed only once.
+ */
+void vc_prover_free(struct VcProver *prover);
+
+/**
+ * Prove a `VcProveInput` given as JSON; the compressed proof is written to `proof`.
+ *
+ * # Safety
+ * `prover` must be a live handle, `input_json` a NUL-terminated string and
+ * `proof` a valid pointer.
+ */
+int32_t vc_prove(const struct VcProver *prover, const char *input_json, struct VcBuffer *proof);
+
+/**
+ * Check a compressed proof against a `.vk` file content and a `VcVerifyInput`
+ * given as JSON. Returns 1 for a valid proof, 0 for an invalid one.
+ *
+ * # Safety
+ * The byte pointers must be valid for their lengths and `input_json` a
+ * NUL-terminated string.
+ */
+int32_t vc_verify(const uint8_t *vk,
+                  size_t vk_len,
+                  const uint8_t *proof,
+                  size_t proof_len,
+                  const char *input_json);
+
+/**
+ * Encrypt the credential of a `VcProveInput` given as JSON into its storage
+ * leaves, `VC::chunks` times `CHUNK_LEN` bytes, written to `leaf`.
+ *
+ * # Safety
+ * `input_json` must be a NUL-terminated string and `leaf` a valid pointer.
+ */
+int32_t vc_encrypt_leaf(const char *input_json, struct VcBuffer *leaf);
+
+/**
+ * Write the merkle root implied by the leaf and proof of a `VcProveInput`
+ * given as JSON to the 32 bytes at `root`.
+ *
+ * # Safety
+ * `input_json` must be a NUL-terminated string and `root` valid for 32 bytes.
+ */
+int32_t vc_merkle_root(const char *input_json, uint8_t *root);
+
+#endif  /* VC_PROVE_H */
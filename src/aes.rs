@@ -1,6 +1,14 @@
-use aes::Aes128;
+use aes::{Aes128, Aes256};
+use aes_gcm::{aead::AeadInPlace, Aes128Gcm, Aes256Gcm, KeyInit};
 use ctr::cipher::{KeyIvInit, StreamCipher};
 use ctr::Ctr32BE;
+use hkdf::Hkdf;
+use num_bigint::BigInt as CircomBigInt;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use zeroize::Zeroize;
+
+use crate::{types::ByteArray, Signal};
 
 pub fn encrypt<const N: usize>(key: &[u8; 16], iv: &[u8; 16], plaintext: &[u8; N]) -> [u8; N] {
     let mut cipher = Ctr32BE::<Aes128>::new(key.into(), iv.into());
@@ -18,3 +26,308 @@ pub fn encrypt<const N: usize>(key: &[u8; 16], iv: &[u8; 16], plaintext: &[u8; N
     answer.copy_from_slice(&ciphertext[..]);
     answer
 }
+
+/// An AES-128 or AES-256 key, hex encoded like [`ByteArray`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum AesKey {
+    Aes128(ByteArray<16>),
+    Aes256(ByteArray<32>),
+}
+
+impl AesKey {
+    pub(crate) fn bytes(&self) -> &[u8] {
+        match self {
+            AesKey::Aes128(key) => key.as_ref(),
+            AesKey::Aes256(key) => key.as_ref(),
+        }
+    }
+}
+
+impl Zeroize for AesKey {
+    fn zeroize(&mut self) {
+        match self {
+            AesKey::Aes128(key) => key.zeroize(),
+            AesKey::Aes256(key) => key.zeroize(),
+        }
+    }
+}
+
+impl From<ByteArray<16>> for AesKey {
+    fn from(key: ByteArray<16>) -> Self {
+        AesKey::Aes128(key)
+    }
+}
+
+impl From<ByteArray<32>> for AesKey {
+    fn from(key: ByteArray<32>) -> Self {
+        AesKey::Aes256(key)
+    }
+}
+
+impl Signal for AesKey {
+    fn to_signal(&self) -> Vec<CircomBigInt> {
+        match self {
+            AesKey::Aes128(key) => key.to_signal(),
+            AesKey::Aes256(key) => key.to_signal(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AesMode {
+    /// Counter mode with the 16-byte IV as the initial counter block.
+    #[default]
+    Ctr,
+    /// GCM with the first 12 bytes of the IV as the nonce; the last 4 bytes
+    /// must be zero. The 16-byte tag is stored in the leaf.
+    Gcm,
+}
+
+impl AesMode {
+    /// Fails if `iv` cannot be used in this mode, see [`AesMode::Gcm`].
+    pub fn check_iv(&self, iv: &[u8; 16]) -> Result<(), String> {
+        match self {
+            AesMode::Ctr => Ok(()),
+            AesMode::Gcm => gcm_nonce(iv).map(|_| ()),
+        }
+    }
+}
+
+/// The cipher of a storage leaf, recorded as a byte in the leaf so readers
+/// know how to decrypt it. Leaves from before the identifier existed have a
+/// zero there, which is AES-128-CTR.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum LeafCipher {
+    Aes128Ctr = 0,
+    Aes256Ctr = 1,
+    Aes128Gcm = 2,
+    Aes256Gcm = 3,
+}
+
+impl LeafCipher {
+    pub fn new(key: &AesKey, mode: AesMode) -> Self {
+        match (key, mode) {
+            (AesKey::Aes128(_), AesMode::Ctr) => LeafCipher::Aes128Ctr,
+            (AesKey::Aes256(_), AesMode::Ctr) => LeafCipher::Aes256Ctr,
+            (AesKey::Aes128(_), AesMode::Gcm) => LeafCipher::Aes128Gcm,
+            (AesKey::Aes256(_), AesMode::Gcm) => LeafCipher::Aes256Gcm,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Option<Self> {
+        [
+            LeafCipher::Aes128Ctr,
+            LeafCipher::Aes256Ctr,
+            LeafCipher::Aes128Gcm,
+            LeafCipher::Aes256Gcm,
+        ]
+        .into_iter()
+        .find(|cipher| *cipher as u8 == id)
+    }
+
+    pub fn id(&self) -> u8 {
+        *self as u8
+    }
+
+    pub fn mode(&self) -> AesMode {
+        match self {
+            LeafCipher::Aes128Ctr | LeafCipher::Aes256Ctr => AesMode::Ctr,
+            LeafCipher::Aes128Gcm | LeafCipher::Aes256Gcm => AesMode::Gcm,
+        }
+    }
+}
+
+fn gcm_nonce(iv: &[u8; 16]) -> Result<&[u8], String> {
+    if iv[12..] != [0u8; 4] {
+        return Err("The last 4 bytes of a GCM IV must be zero".to_string());
+    }
+    Ok(&iv[..12])
+}
+
+/// Encrypt in place, returning the GCM tag, or zeros in counter mode.
+fn apply(key: &AesKey, mode: AesMode, iv: &[u8; 16], data: &mut [u8]) -> Result<[u8; 16], String> {
+    let key = key.bytes();
+    match mode {
+        AesMode::Ctr => {
+            match key.len() {
+                16 => Ctr32BE::<Aes128>::new(key.into(), iv.into()).apply_keystream(data),
+                _ => Ctr32BE::<Aes256>::new(key.into(), iv.into()).apply_keystream(data),
+            }
+            Ok([0u8; 16])
+        }
+        AesMode::Gcm => {
+            let nonce = gcm_nonce(iv)?.into();
+            let tag = match key.len() {
+                16 => Aes128Gcm::new(key.into()).encrypt_in_place_detached(nonce, b"", data),
+                _ => Aes256Gcm::new(key.into()).encrypt_in_place_detached(nonce, b"", data),
+            };
+            Ok(tag.map_err(|e| format!("Cannot encrypt: {:?}", e))?.into())
+        }
+    }
+}
+
+/// Encrypt a leaf payload, returning the ciphertext and the tag, which is
+/// zero in counter mode.
+pub fn encrypt_leaf(
+    key: &AesKey,
+    mode: AesMode,
+    iv: &[u8; 16],
+    plaintext: &[u8],
+) -> Result<(Vec<u8>, [u8; 16]), String> {
+    let mut ciphertext = plaintext.to_vec();
+    let tag = apply(key, mode, iv, &mut ciphertext)?;
+    Ok((ciphertext, tag))
+}
+
+/// Decrypt a leaf payload, checking the tag in GCM mode.
+pub fn decrypt_leaf(
+    key: &AesKey,
+    mode: AesMode,
+    iv: &[u8; 16],
+    ciphertext: &[u8],
+    tag: &[u8; 16],
+) -> Result<Vec<u8>, String> {
+    let mut plaintext = ciphertext.to_vec();
+    match mode {
+        AesMode::Ctr => {
+            apply(key, mode, iv, &mut plaintext)?;
+        }
+        AesMode::Gcm => {
+            let nonce = gcm_nonce(iv)?.into();
+            let key = key.bytes();
+            let result = match key.len() {
+                16 => Aes128Gcm::new(key.into()).decrypt_in_place_detached(
+                    nonce,
+                    b"",
+                    &mut plaintext,
+                    tag.into(),
+                ),
+                _ => Aes256Gcm::new(key.into()).decrypt_in_place_detached(
+                    nonce,
+                    b"",
+                    &mut plaintext,
+                    tag.into(),
+                ),
+            };
+            result.map_err(|_| "Authentication tag mismatch".to_string())?;
+        }
+    }
+    Ok(plaintext)
+}
+
+const DERIVATION_SALT: &[u8] = b"vc-prove leaf cipher";
+
+/// Derive the AES-128 key and IV of a credential from a master secret, so the
+/// holder stores one secret instead of a key per credential. HKDF-SHA256
+/// (RFC 5869) with the salt `"vc-prove leaf cipher"`, the master secret as
+/// input key material and the credential id as info; the 32 output bytes are
+/// the key then the IV. [`VcProveInput::from_master_secret`] passes the
+/// hex-decoded serial number bytes, not the hex string. Credential ids must be
+/// unique, or two leaves share a key and IV.
+///
+/// [`VcProveInput::from_master_secret`]: crate::types::VcProveInput::from_master_secret
+pub fn derive_key_iv(
+    master_secret: &ByteArray<32>,
+    credential_id: &[u8],
+) -> (ByteArray<16>, ByteArray<16>) {
+    let hkdf = Hkdf::<Sha256>::new(Some(DERIVATION_SALT), master_secret.as_ref());
+    let mut okm = [0u8; 32];
+    hkdf.expand(credential_id, &mut okm).unwrap();
+    (
+        ByteArray::new(okm[..16].try_into().unwrap()),
+        ByteArray::new(okm[16..].try_into().unwrap()),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn keys() -> [AesKey; 2] {
+        [
+            ByteArray::new(*b"verysecretkey123").into(),
+            ByteArray::new(*b"a much longer secret key for 256").into(),
+        ]
+    }
+
+    #[test]
+    fn leaf_cipher_roundtrip() {
+        let iv = *b"uniqueiv1234\0\0\0\0";
+        let plaintext = [42u8; 111];
+        for key in keys() {
+            for mode in [AesMode::Ctr, AesMode::Gcm] {
+                let cipher = LeafCipher::new(&key, mode);
+                assert_eq!(LeafCipher::from_id(cipher.id()), Some(cipher));
+                assert_eq!(cipher.mode(), mode);
+
+                let (ciphertext, tag) = encrypt_leaf(&key, mode, &iv, &plaintext).unwrap();
+                assert_ne!(ciphertext, plaintext);
+                assert_eq!(
+                    decrypt_leaf(&key, mode, &iv, &ciphertext, &tag).unwrap(),
+                    plaintext
+                );
+
+                let mut tampered = ciphertext;
+                tampered[3] ^= 1;
+                let decrypted = decrypt_leaf(&key, mode, &iv, &tampered, &tag);
+                match mode {
+                    AesMode::Ctr => assert_ne!(decrypted.unwrap(), plaintext),
+                    AesMode::Gcm => assert!(decrypted.is_err()),
+                }
+            }
+        }
+        assert_eq!(LeafCipher::from_id(4), None);
+    }
+
+    // Computed independently with Python's hmac and hashlib.
+    #[test]
+    fn derive_key_iv_vectors() {
+        let vectors = [
+            (
+                [0u8; 32],
+                &b"1234567890"[..],
+                "71137a039984487397b06729fba1bad6",
+                "c909a53b4762ecd55b891266a4b698a4",
+            ),
+            (
+                core::array::from_fn(|i| i as u8),
+                b"1234567890",
+                "3863d7952aaf9775085bebf0fa21f1a1",
+                "d613df8dae58a5511052b7c62807276c",
+            ),
+            (
+                core::array::from_fn(|i| i as u8),
+                b"1234567891",
+                "21577628d216b12572a21b0d525892a8",
+                "b09f4e79be873eee48d0ac935ebffdf4",
+            ),
+            // The hex-decoded serial of `Sample::vc()`, as the mock input uses.
+            (
+                core::array::from_fn(|i| i as u8),
+                &[0x12, 0x34, 0x56, 0x78, 0x90],
+                "c69fec1cb433c207988917f2e2611549",
+                "30f97eeb17ecd25b3bc405c69d0b207a",
+            ),
+        ];
+        for (master_secret, id, key, iv) in vectors {
+            let (derived_key, derived_iv) = derive_key_iv(&ByteArray::new(master_secret), id);
+            assert_eq!(hex::encode(AsRef::<[u8]>::as_ref(&derived_key)), key);
+            assert_eq!(hex::encode(AsRef::<[u8]>::as_ref(&derived_iv)), iv);
+        }
+    }
+
+    #[test]
+    fn aes128_ctr_matches_encrypt() {
+        let iv = *b"uniqueiv12345678";
+        let plaintext = [7u8; 111];
+        let key = ByteArray::new(*b"verysecretkey123");
+        let (ciphertext, tag) =
+            encrypt_leaf(&key.clone().into(), AesMode::Ctr, &iv, &plaintext).unwrap();
+        assert_eq!(ciphertext, encrypt(key.as_ref(), &iv, &plaintext));
+        assert_eq!(tag, [0u8; 16]);
+        assert!(encrypt_leaf(&key.into(), AesMode::Gcm, &iv, &plaintext).is_err());
+    }
+}
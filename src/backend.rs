@@ -0,0 +1,123 @@
+//! Groth16 implementations behind one interface, so the libsnark prover can be
+//! driven, checked and compared on the same circuit and witness as arkworks.
+
+use ark_bn254::{Bn254, Fr};
+use ark_circom::CircomCircuit;
+use ark_groth16::{prepare_verifying_key, Proof, ProvingKey};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+use rand::thread_rng;
+
+use crate::{
+    groth16::Groth16,
+    verifier::{proof_from_bytes, proof_to_bytes},
+};
+
+/// The arkworks prover, on the GPU with the `cuda` feature.
+pub type Arkworks = Groth16;
+
+pub trait ProvingBackend {
+    type ProvingKey;
+    type Proof;
+
+    /// Whether the witness of `circuit` satisfies this backend's translation
+    /// of its constraints.
+    fn is_satisfied(circuit: CircomCircuit<Bn254>) -> Result<bool, String>;
+
+    fn setup(circuit: CircomCircuit<Bn254>) -> Result<Self::ProvingKey, String>;
+
+    fn prove(pk: &Self::ProvingKey, circuit: CircomCircuit<Bn254>) -> Result<Self::Proof, String>;
+
+    /// `public_inputs` excludes the leading constant one, as returned by
+    /// `CircomCircuit::get_public_inputs`.
+    fn verify(
+        pk: &Self::ProvingKey,
+        public_inputs: &[Fr],
+        proof: &Self::Proof,
+    ) -> Result<bool, String>;
+
+    /// The arkworks encoding is [`crate::verifier::proof_to_bytes`]; libsnark
+    /// proofs cannot be serialized yet and return an error.
+    fn serialize_proof(proof: &Self::Proof) -> Result<Vec<u8>, String>;
+
+    fn deserialize_proof(raw: &[u8]) -> Result<Self::Proof, String>;
+}
+
+impl ProvingBackend for Arkworks {
+    type ProvingKey = ProvingKey<Bn254>;
+    type Proof = Proof<Bn254>;
+
+    fn is_satisfied(circuit: CircomCircuit<Bn254>) -> Result<bool, String> {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit
+            .generate_constraints(cs.clone())
+            .and_then(|_| cs.is_satisfied())
+            .map_err(|e| format!("Cannot synthesize circuit: {:?}", e))
+    }
+
+    fn setup(circuit: CircomCircuit<Bn254>) -> Result<Self::ProvingKey, String> {
+        Groth16::generate_random_parameters_with_reduction(circuit, &mut thread_rng())
+            .map_err(|e| format!("Cannot generate params: {:?}", e))
+    }
+
+    fn prove(pk: &Self::ProvingKey, circuit: CircomCircuit<Bn254>) -> Result<Self::Proof, String> {
+        Groth16::create_random_proof_with_reduction(circuit, pk, &mut thread_rng())
+            .map_err(|e| format!("Cannot prove: {:?}", e))
+    }
+
+    fn verify(
+        pk: &Self::ProvingKey,
+        public_inputs: &[Fr],
+        proof: &Self::Proof,
+    ) -> Result<bool, String> {
+        ark_groth16::Groth16::<Bn254>::verify_proof(
+            &prepare_verifying_key(&pk.vk),
+            proof,
+            public_inputs,
+        )
+        .map_err(|e| format!("Cannot verify: {:?}", e))
+    }
+
+    fn serialize_proof(proof: &Self::Proof) -> Result<Vec<u8>, String> {
+        Ok(proof_to_bytes(proof))
+    }
+
+    fn deserialize_proof(raw: &[u8]) -> Result<Self::Proof, String> {
+        proof_from_bytes(raw)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use crate::groth16::test::square_r1cs;
+
+    pub(crate) fn square_circuit(x: u64, y: u64) -> CircomCircuit<Bn254> {
+        CircomCircuit {
+            r1cs: square_r1cs(),
+            witness: Some(vec![Fr::from(1u64), Fr::from(y), Fr::from(x)]),
+        }
+    }
+
+    /// Setup, prove and verify on the square circuit.
+    pub(crate) fn check_backend<B: ProvingBackend>() {
+        assert!(B::is_satisfied(square_circuit(3, 9)).unwrap());
+        assert!(!B::is_satisfied(square_circuit(3, 10)).unwrap());
+
+        let pk = B::setup(square_circuit(3, 9)).unwrap();
+        let proof = B::prove(&pk, square_circuit(3, 9)).unwrap();
+        assert!(B::verify(&pk, &[Fr::from(9u64)], &proof).unwrap());
+        assert!(!B::verify(&pk, &[Fr::from(10u64)], &proof).unwrap());
+    }
+
+    #[test]
+    fn arkworks_backend() {
+        check_backend::<Arkworks>();
+
+        let pk = Arkworks::setup(square_circuit(3, 9)).unwrap();
+        let proof = Arkworks::prove(&pk, square_circuit(3, 9)).unwrap();
+        let raw = Arkworks::serialize_proof(&proof).unwrap();
+        let decoded = Arkworks::deserialize_proof(&raw).unwrap();
+        assert_eq!(decoded, proof);
+        assert!(Arkworks::verify(&pk, &[Fr::from(9u64)], &decoded).unwrap());
+    }
+}
@@ -1,12 +1,13 @@
-use std::fs::File;
+use std::time::Instant;
 
+use ark_bn254::Bn254;
 use ark_groth16::prepare_verifying_key;
-use ark_serialize::CanonicalDeserialize;
 use vc_prove::{
     circuit::circom_builder,
     get_zk_task_input, get_zk_task_name,
-    groth16::{prove, setup, verify},
-    params::load_proving_key,
+    groth16::{setup, verify, Prover},
+    params::load_verifying_key,
+    resident_memory,
     sample::Sample,
     types::VcProveInput,
     warmup_current_thread,
@@ -19,31 +20,37 @@ fn main() {
     let task_name = get_zk_task_name();
     let command_input = get_zk_task_input();
 
-    let circom = circom_builder(&"output".into(), &task_name);
+    let circom = circom_builder::<Bn254>(&"output".into(), &task_name);
 
     // 1. Setup Params
-    let pk;
+    let prover;
     let vk;
     if LOAD_PARAMS {
         println!("Load params");
 
         const CHECK_PARAMS: bool = false;
-        pk = load_proving_key::<CHECK_PARAMS>(&"output".into(), &task_name).unwrap();
-        vk = CanonicalDeserialize::deserialize_uncompressed(
-            File::open(format!("output/{task_name}.vk")).unwrap(),
-        )
-        .unwrap();
+        let start = Instant::now();
+        prover = Prover::load::<CHECK_PARAMS>(&"output".into(), &task_name, circom).unwrap();
+        println!("Load proving key time {:?}", start.elapsed());
+        if let Some(rss) = resident_memory() {
+            println!("Resident memory {} MiB", rss >> 20);
+        }
+        vk = load_verifying_key(&"output".into(), &task_name).unwrap();
     } else {
         println!("Generate params");
-        pk = setup(&circom).unwrap();
+        let pk = setup(&circom).unwrap();
         vk = prepare_verifying_key(&pk.vk);
+        prover = Prover::new(pk, circom).unwrap();
     }
 
     // 2. Prove
     println!("Prove");
     let input = command_input.clone().unwrap_or_else(|| Sample::input());
-    println!("{}", &serde_json::to_string_pretty(&input).unwrap());
-    let proof = prove(&pk, &circom, input).unwrap();
+    println!(
+        "{}",
+        &serde_json::to_string_pretty(&input.to_verify_input()).unwrap()
+    );
+    let proof = prover.prove(input).unwrap();
 
     // 3. Verify
     println!("Verify");
@@ -0,0 +1,53 @@
+use std::path::PathBuf;
+
+use vc_prove::{
+    aes::AesMode,
+    issuance::{Issuer, IvRegistry},
+    types::{HashMode, VC},
+};
+
+fn usage() -> ! {
+    eprintln!("Usage:");
+    eprintln!("  issue <vc json> <name> [--dir <dir>] [--aes256] [--gcm]");
+    eprintln!();
+    eprintln!("Writes <dir>/<name>.leaf for upload and <dir>/<name>.holder.json for the");
+    eprintln!("holder. The holder file holds the key and IV in the clear and is created");
+    eprintln!("readable by its owner only (mode 0600 on unix); deliver it privately.");
+    eprintln!("Key and IV pairs are recorded in <dir>/iv_registry and never reused.");
+    std::process::exit(1);
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() < 3 {
+        usage();
+    }
+    let (vc_path, name) = (&args[1], &args[2]);
+
+    let mut dir: PathBuf = "issued".into();
+    let (mut aes256, mut aes_mode) = (false, AesMode::Ctr);
+    let mut rest = args[3..].iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--dir" => dir = rest.next().unwrap_or_else(|| usage()).into(),
+            "--aes256" => aes256 = true,
+            "--gcm" => aes_mode = AesMode::Gcm,
+            _ => usage(),
+        }
+    }
+
+    let json = std::fs::read_to_string(vc_path).expect("Cannot read VC");
+    let vc = VC::from_json(&json).expect("Invalid VC");
+    std::fs::create_dir_all(&dir).expect("Cannot create output directory");
+
+    let registry = IvRegistry::open(&dir.join("iv_registry")).unwrap();
+    let mut issuer = Issuer::new(registry).with_cipher(aes256, aes_mode);
+    let issued = issuer.issue(&mut rand::thread_rng(), vc).unwrap();
+    issued.save(&dir, name).unwrap();
+
+    println!(
+        "Leaf {} bytes, hash {:?}",
+        issued.leaf.len(),
+        issued.bundle.leaf_hash(HashMode::Keccak).unwrap()
+    );
+}
@@ -11,7 +11,7 @@ fn main() {
     let name = get_zk_task_name();
     let command_input = get_zk_task_input();
 
-    let mut circom = circom_builder(&"output".into(), &name);
+    let mut circom = circom_builder::<ark_bn254::Bn254>(&"output".into(), &name);
     let input = command_input.unwrap_or_else(|| Sample::input());
     circom.inputs = input.to_inputs();
 
@@ -0,0 +1,80 @@
+use std::{fs::File, io::BufReader, path::PathBuf};
+
+use ark_bn254::Bn254;
+use rand::{rngs::StdRng, RngCore, SeedableRng};
+use sha2::{Digest, Sha256};
+use vc_prove::{
+    circuit::load_r1cs,
+    phase2::{domain_size, initialize, Phase2Params},
+    snarkjs::read_ptau,
+};
+
+fn usage() -> ! {
+    eprintln!("Usage:");
+    eprintln!("  phase2 init <name> <ptau file>");
+    eprintln!("  phase2 contribute <name> [entropy]");
+    eprintln!("  phase2 verify <name> <ptau file>");
+    std::process::exit(1);
+}
+
+fn initial_params(dir: &PathBuf, name: &str, ptau: &str) -> Phase2Params {
+    let r1cs = load_r1cs::<Bn254>(dir, name);
+    let reader = BufReader::new(File::open(ptau).expect("Cannot open ptau"));
+    let bases = read_ptau(reader, domain_size(&r1cs)).unwrap();
+    Phase2Params::new(initialize(&bases, &r1cs).unwrap())
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() < 3 {
+        usage();
+    }
+    let (command, name) = (&args[1], &args[2]);
+    let dir: PathBuf = "output".into();
+
+    match command.as_str() {
+        "init" => {
+            let ptau = args.get(3).unwrap_or_else(|| usage());
+            let params = initial_params(&dir, name, ptau);
+            params.save(&dir, name).unwrap();
+            println!("Initial parameters hash {}", hex::encode(params.cs_hash));
+        }
+        "contribute" => {
+            let mut params = Phase2Params::load(&dir, name).unwrap();
+
+            // Mix user supplied entropy with the OS randomness.
+            let mut seed = [0u8; 32];
+            rand::thread_rng().fill_bytes(&mut seed);
+            let mut hasher = Sha256::new();
+            hasher.update(seed);
+            hasher.update(args.get(3).map_or(&[][..], |x| x.as_bytes()));
+            seed.copy_from_slice(&hasher.finalize());
+
+            let hash = params.contribute(&mut StdRng::from_seed(seed));
+            params.save(&dir, name).unwrap();
+            println!(
+                "Contribution #{} hash {}",
+                params.contributions.len(),
+                hex::encode(hash)
+            );
+        }
+        "verify" => {
+            let ptau = args.get(3).unwrap_or_else(|| usage());
+            let initial = initial_params(&dir, name, ptau);
+            let params = Phase2Params::load(&dir, name).unwrap();
+            match params.verify(&initial.pk) {
+                Ok(hashes) => {
+                    for (i, hash) in hashes.iter().enumerate() {
+                        println!("Contribution #{} hash {}", i + 1, hex::encode(hash));
+                    }
+                    println!("Done");
+                }
+                Err(e) => {
+                    eprintln!("Verification failed: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        _ => usage(),
+    }
+}
@@ -1,21 +1,21 @@
-use std::{fs::File, time::Instant};
+use std::time::Instant;
 
 use ark_bn254::Bn254;
-use ark_groth16::{prepare_verifying_key, PreparedVerifyingKey};
-use ark_serialize::CanonicalDeserialize;
+use ark_groth16::prepare_verifying_key;
 
 use vc_prove::{
     circuit::circom_builder,
     get_zk_task_name,
     groth16::setup,
-    params::{load_proving_key, save_key},
+    params::{load_proving_key, load_verifying_key, save_key},
+    resident_memory,
 };
 
 fn main() {
     let name = get_zk_task_name();
 
     let start = Instant::now();
-    let circom = circom_builder(&"output".into(), &name);
+    let circom = circom_builder::<Bn254>(&"output".into(), &name);
     println!("Load circuit time {:?}", start.elapsed());
 
     let pk = setup(&circom).unwrap();
@@ -26,16 +26,18 @@ fn main() {
 
     println!("Start load");
     let start = Instant::now();
-    let reader = File::open(format!("output/{name}.vk")).unwrap();
-    let load_vk = PreparedVerifyingKey::<Bn254>::deserialize_uncompressed(reader).unwrap();
+    let load_vk = load_verifying_key(&"output".into(), &name).unwrap();
     println!("Load verifing key time {:?}", start.elapsed());
     if load_vk != vk {
         panic!("incorrect vk");
     }
 
     let start = Instant::now();
-    let load_pk = load_proving_key::<false>(&"output".into(), &name).unwrap();
+    let load_pk = load_proving_key::<Bn254, false>(&"output".into(), &name).unwrap();
     println!("Load proving key time {:?}", start.elapsed());
+    if let Some(rss) = resident_memory() {
+        println!("Resident memory {} MiB", rss >> 20);
+    }
     if load_pk != pk {
         panic!("incorrect pk");
     }
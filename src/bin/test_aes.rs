@@ -1,6 +1,8 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, path::PathBuf};
 
 use aes::Aes128;
+use ark_bls12_381::Bls12_381;
+use ark_bn254::Bn254;
 use ark_groth16::prepare_verifying_key;
 use ctr::cipher::{KeyIvInit, StreamCipher};
 use ctr::Ctr32BE;
@@ -8,7 +10,7 @@ use rand::Rng;
 use vc_prove::types::ByteArray;
 use vc_prove::{
     circuit::circom_builder,
-    groth16::{prove, setup, verify},
+    groth16::{prove, setup, verify, Engine},
     warmup_current_thread, Signal,
 };
 
@@ -36,8 +38,7 @@ fn encrypt<const N: usize>(key: &[u8; 16], iv: &[u8; 16], plaintext: &[u8; N]) -
     answer
 }
 
-fn main() {
-    warmup_current_thread();
+fn run<E: Engine>(dir: &PathBuf) {
     let task_name = "test_aes";
 
     let key = b"verysecretkey123";
@@ -53,7 +54,7 @@ fn main() {
     input.insert("iv", ByteArray::new(*iv).to_signal());
     input.insert("key", ByteArray::new(*key).to_signal());
 
-    let circom = circom_builder(&"output".into(), &task_name);
+    let circom = circom_builder::<E>(dir, task_name);
 
     // 1. Setup Params
     println!("Generate params");
@@ -72,3 +73,14 @@ fn main() {
 
     println!("Done");
 }
+
+fn main() {
+    warmup_current_thread();
+    // Circuits for BLS12-381 are built into `output/bls12381` by
+    // `./build_circuit.sh test_aes`.
+    match std::env::args().nth(1).as_deref() {
+        None | Some("bn254") => run::<Bn254>(&"output".into()),
+        Some("bls12381") => run::<Bls12_381>(&"output/bls12381".into()),
+        Some(curve) => panic!("Unsupported curve {}", curve),
+    }
+}
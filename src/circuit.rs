@@ -1,10 +1,14 @@
 use std::{
-    fs::{self},
+    fs::{self, File},
+    io::BufReader,
     path::PathBuf,
 };
 
-use ark_bn254::Bn254;
-use ark_circom::{CircomBuilder, CircomConfig};
+use ark_circom::{
+    circom::{R1CSFile, R1CS},
+    CircomBuilder, CircomConfig,
+};
+use ark_ec::pairing::Pairing;
 
 pub fn check_file(file_path: &PathBuf) {
     if !fs::metadata(file_path).is_ok() {
@@ -14,13 +18,46 @@ pub fn check_file(file_path: &PathBuf) {
     }
 }
 
-pub fn circom_builder(current_dir: &PathBuf, name: &str) -> CircomBuilder<Bn254> {
+/// Load `{name}.r1cs` and the witness generator, compiled for the scalar field
+/// of `E` (`circom --prime bls12381` for BLS12-381).
+pub fn circom_builder<E: Pairing>(current_dir: &PathBuf, name: &str) -> CircomBuilder<E> {
     let wtns = current_dir.join(format!("{name}_js/{name}.wasm", name = name));
     let r1cs = current_dir.join(format!("{name}.r1cs", name = name));
     check_file(&wtns);
     check_file(&r1cs);
 
-    let mut circom_config = CircomConfig::<Bn254>::new(wtns, r1cs).expect("Cannot parse circom");
+    let mut circom_config = CircomConfig::<E>::new(wtns, r1cs).expect("Cannot parse circom");
     circom_config.sanity_check = true;
     CircomBuilder::new(circom_config)
 }
+
+/// Like [`circom_builder`], but reports missing or malformed files instead of
+/// exiting the process, for library callers.
+pub fn try_circom_builder<E: Pairing>(
+    current_dir: &PathBuf,
+    name: &str,
+) -> Result<CircomBuilder<E>, String> {
+    let wtns = current_dir.join(format!("{name}_js/{name}.wasm", name = name));
+    let r1cs = current_dir.join(format!("{name}.r1cs", name = name));
+    for file_path in [&wtns, &r1cs] {
+        if !file_path.exists() {
+            return Err(format!("File '{:?}' does not exist", file_path));
+        }
+    }
+
+    let mut circom_config =
+        CircomConfig::<E>::new(wtns, r1cs).map_err(|e| format!("Cannot parse circom: {:?}", e))?;
+    circom_config.sanity_check = true;
+    Ok(CircomBuilder::new(circom_config))
+}
+
+/// Load only the constraint system, for tools that never compute witnesses.
+pub fn load_r1cs<E: Pairing>(current_dir: &PathBuf, name: &str) -> R1CS<E> {
+    let r1cs = current_dir.join(format!("{name}.r1cs", name = name));
+    check_file(&r1cs);
+
+    let reader = BufReader::new(File::open(r1cs).expect("Cannot open r1cs"));
+    R1CSFile::<E>::new(reader)
+        .expect("Cannot parse r1cs")
+        .into()
+}
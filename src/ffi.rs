@@ -0,0 +1,270 @@
+//! C ABI over the JSON input types, for callers in other languages. Build with
+//! the `ffi` feature; the header is `include/vc_prove.h`.
+//!
+//! Functions returning `int32_t` return a negative value on failure, and the
+//! message is then available from [`vc_last_error`] on the same thread. Byte
+//! outputs are returned in a [`VcBuffer`] owned by the caller, who releases it
+//! with [`vc_buffer_free`].
+
+use std::{
+    cell::RefCell,
+    ffi::{c_char, CStr, CString},
+    panic::{catch_unwind, AssertUnwindSafe},
+    path::PathBuf,
+    ptr, slice,
+};
+
+use crate::{
+    circuit::try_circom_builder,
+    groth16::Prover,
+    params::read_verifying_key,
+    types::{VcProveInput, VcVerifyInput},
+    verifier::{proof_from_bytes, proof_to_bytes, verify},
+};
+use ark_bn254::Bn254;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// A byte buffer allocated by this library.
+#[repr(C)]
+pub struct VcBuffer {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+impl VcBuffer {
+    fn from_vec(bytes: Vec<u8>) -> Self {
+        let mut bytes = bytes.into_boxed_slice();
+        let buffer = Self {
+            data: bytes.as_mut_ptr(),
+            len: bytes.len(),
+        };
+        std::mem::forget(bytes);
+        buffer
+    }
+}
+
+/// A loaded proving key and circuit. Not thread-safe: use one handle per thread.
+pub struct VcProver(Prover);
+
+fn set_last_error(message: String) {
+    let message = CString::new(message.replace('\0', " ")).unwrap();
+    LAST_ERROR.with(|e| *e.borrow_mut() = Some(message));
+}
+
+/// Run `f`, turning errors and panics into `on_error` plus the last error.
+/// Unwinding must not cross the C boundary; a prover that panicked is still
+/// usable since proving only reads it.
+fn guard<T>(on_error: T, f: impl FnOnce() -> Result<T, String>) -> T {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(value)) => value,
+        Ok(Err(message)) => {
+            set_last_error(message);
+            on_error
+        }
+        Err(_) => {
+            set_last_error("Internal panic".into());
+            on_error
+        }
+    }
+}
+
+unsafe fn read_str<'a>(input: *const c_char, what: &str) -> Result<&'a str, String> {
+    if input.is_null() {
+        return Err(format!("{} is null", what));
+    }
+    CStr::from_ptr(input)
+        .to_str()
+        .map_err(|e| format!("{} is not UTF-8: {}", what, e))
+}
+
+unsafe fn read_bytes<'a>(data: *const u8, len: usize, what: &str) -> Result<&'a [u8], String> {
+    if data.is_null() {
+        return Err(format!("{} is null", what));
+    }
+    Ok(slice::from_raw_parts(data, len))
+}
+
+unsafe fn read_prove_input(input_json: *const c_char) -> Result<VcProveInput, String> {
+    serde_json::from_str(read_str(input_json, "input")?)
+        .map_err(|e| format!("Invalid prove input: {}", e))
+}
+
+/// Message of the last failed call on this thread, or null. The pointer stays
+/// valid until the next call into this library on the same thread.
+#[no_mangle]
+pub extern "C" fn vc_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| e.borrow().as_ref().map_or(ptr::null(), |e| e.as_ptr()))
+}
+
+/// # Safety
+/// `buffer` must come from this library and be freed only once.
+#[no_mangle]
+pub unsafe extern "C" fn vc_buffer_free(buffer: VcBuffer) {
+    if !buffer.data.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+            buffer.data,
+            buffer.len,
+        )));
+    }
+}
+
+/// Load `{dir}/{name}.pk` and the circuit files next to it. Returns null on failure.
+///
+/// # Safety
+/// `dir` and `name` must be NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn vc_prover_load(dir: *const c_char, name: *const c_char) -> *mut VcProver {
+    guard(ptr::null_mut(), || {
+        let dir: PathBuf = read_str(dir, "dir")?.into();
+        let name = read_str(name, "name")?;
+        let circom = try_circom_builder(&dir, name)?;
+        let prover = Prover::load::<false>(&dir, name, circom)?;
+        Ok(Box::into_raw(Box::new(VcProver(prover))))
+    })
+}
+
+/// # Safety
+/// `prover` must come from [`vc_prover_load`] and be freed only once.
+#[no_mangle]
+pub unsafe extern "C" fn vc_prover_free(prover: *mut VcProver) {
+    if !prover.is_null() {
+        drop(Box::from_raw(prover));
+    }
+}
+
+/// Prove a `VcProveInput` given as JSON; the compressed proof is written to `proof`.
+///
+/// # Safety
+/// `prover` must be a live handle, `input_json` a NUL-terminated string and
+/// `proof` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn vc_prove(
+    prover: *const VcProver,
+    input_json: *const c_char,
+    proof: *mut VcBuffer,
+) -> i32 {
+    guard(-1, || {
+        let prover = prover.as_ref().ok_or("prover is null")?;
+        let input = read_prove_input(input_json)?;
+        if proof.is_null() {
+            return Err("proof is null".into());
+        }
+        let output = prover.0.prove(input)?;
+        *proof = VcBuffer::from_vec(proof_to_bytes(&output));
+        Ok(0)
+    })
+}
+
+/// Check a compressed proof against a `.vk` file content and a `VcVerifyInput`
+/// given as JSON. Returns 1 for a valid proof, 0 for an invalid one.
+///
+/// # Safety
+/// The byte pointers must be valid for their lengths and `input_json` a
+/// NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn vc_verify(
+    vk: *const u8,
+    vk_len: usize,
+    proof: *const u8,
+    proof_len: usize,
+    input_json: *const c_char,
+) -> i32 {
+    guard(-1, || {
+        let (_, vk) = read_verifying_key::<Bn254>(read_bytes(vk, vk_len, "vk")?)
+            .map_err(|e| format!("Cannot load verifying key: {:?}", e))?;
+        let proof = proof_from_bytes(read_bytes(proof, proof_len, "proof")?)?;
+        let input: VcVerifyInput = serde_json::from_str(read_str(input_json, "input")?)
+            .map_err(|e| format!("Invalid verify input: {}", e))?;
+        Ok(verify(&vk, &proof, &input)? as i32)
+    })
+}
+
+/// Encrypt the credential of a `VcProveInput` given as JSON into its storage
+/// leaves, `VC::chunks` times `CHUNK_LEN` bytes, written to `leaf`.
+///
+/// # Safety
+/// `input_json` must be a NUL-terminated string and `leaf` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn vc_encrypt_leaf(input_json: *const c_char, leaf: *mut VcBuffer) -> i32 {
+    guard(-1, || {
+        let input = read_prove_input(input_json)?;
+        if leaf.is_null() {
+            return Err("leaf is null".into());
+        }
+        *leaf = VcBuffer::from_vec(input.leaf().to_vec());
+        Ok(0)
+    })
+}
+
+/// Write the merkle root implied by the leaf and proof of a `VcProveInput`
+/// given as JSON to the 32 bytes at `root`.
+///
+/// # Safety
+/// `input_json` must be a NUL-terminated string and `root` valid for 32 bytes.
+#[no_mangle]
+pub unsafe extern "C" fn vc_merkle_root(input_json: *const c_char, root: *mut u8) -> i32 {
+    guard(-1, || {
+        let input = read_prove_input(input_json)?;
+        if root.is_null() {
+            return Err("root is null".into());
+        }
+        ptr::copy_nonoverlapping(input.merkle_root().as_ptr(), root, 32);
+        Ok(0)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{sample::Sample, types::expose_secrets};
+
+    #[test]
+    fn leaf_and_root_through_c_abi() {
+        let input = Sample::input();
+        let json = expose_secrets(|| serde_json::to_string(&input)).unwrap();
+        let json = CString::new(json).unwrap();
+
+        unsafe {
+            let mut leaf = VcBuffer {
+                data: ptr::null_mut(),
+                len: 0,
+            };
+            assert_eq!(vc_encrypt_leaf(json.as_ptr(), &mut leaf), 0);
+            assert_eq!(slice::from_raw_parts(leaf.data, leaf.len), input.leaf());
+            vc_buffer_free(leaf);
+            assert_eq!(vc_encrypt_leaf(json.as_ptr(), ptr::null_mut()), -1);
+            let error = CStr::from_ptr(vc_last_error()).to_str().unwrap();
+            assert_eq!(error, "leaf is null");
+
+            let mut root = [0u8; 32];
+            assert_eq!(vc_merkle_root(json.as_ptr(), root.as_mut_ptr()), 0);
+            assert_eq!(root, input.merkle_root().0);
+
+            let invalid = CString::new("{}").unwrap();
+            assert_eq!(vc_merkle_root(invalid.as_ptr(), root.as_mut_ptr()), -1);
+            let error = CStr::from_ptr(vc_last_error()).to_str().unwrap();
+            assert!(error.starts_with("Invalid prove input"));
+        }
+    }
+
+    #[test]
+    fn verify_through_c_abi() {
+        let vk = include_bytes!("../tests/fixtures/verifier.vk");
+        let proof = include_bytes!("../tests/fixtures/verifier.proof");
+        let input = CString::new(include_str!("../tests/fixtures/verifier_input.json")).unwrap();
+        let call = |proof: &[u8]| unsafe {
+            vc_verify(
+                vk.as_ptr(),
+                vk.len(),
+                proof.as_ptr(),
+                proof.len(),
+                input.as_ptr(),
+            )
+        };
+        assert_eq!(call(proof), 1);
+        assert_eq!(call(&proof[1..]), -1);
+    }
+}
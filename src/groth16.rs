@@ -1,29 +1,87 @@
+use std::path::PathBuf;
+
+use ark_bls12_381::Bls12_381;
 use ark_bn254::Bn254;
-use ark_circom::{CircomBuilder, CircomCircuit};
+use ark_circom::{circom::R1CS, CircomBuilder, CircomCircuit};
+use ark_ec::pairing::Pairing;
 use ark_ff::BigInt;
-use ark_groth16::{PreparedVerifyingKey, Proof, ProvingKey};
-use rand::thread_rng;
+use ark_groth16::{Proof, ProvingKey};
+use ark_relations::r1cs::SynthesisError;
+use rand::{thread_rng, Rng};
+
+use crate::{params::open_proving_key, phase2::domain_size, signal::ProveInput};
 
-use crate::signal::{ProveInput, VerifyInput};
+pub use crate::verifier::verify;
 
 #[cfg(feature = "cuda")]
-type Groth16 = ark_groth16::Groth16<ark_bn254::Bn254, ark_groth16::gpu::GpuDomain<ark_bn254::Fr>>;
+pub(crate) type Groth16 =
+    ark_groth16::Groth16<ark_bn254::Bn254, ark_groth16::gpu::GpuDomain<ark_bn254::Fr>>;
 #[cfg(not(feature = "cuda"))]
-type Groth16 = ark_groth16::Groth16<ark_bn254::Bn254>;
+pub(crate) type Groth16 = ark_groth16::Groth16<ark_bn254::Bn254>;
+
+/// A pairing engine circuits can be proven on. BN254 uses the GPU with the
+/// `cuda` feature, other curves always prove on the CPU.
+pub trait Engine: Pairing {
+    fn generate_parameters(
+        circuit: CircomCircuit<Self>,
+        rng: &mut impl Rng,
+    ) -> Result<ProvingKey<Self>, SynthesisError>;
+
+    fn create_proof(
+        circuit: CircomCircuit<Self>,
+        pk: &ProvingKey<Self>,
+        rng: &mut impl Rng,
+    ) -> Result<Proof<Self>, SynthesisError>;
+}
+
+impl Engine for Bn254 {
+    fn generate_parameters(
+        circuit: CircomCircuit<Self>,
+        rng: &mut impl Rng,
+    ) -> Result<ProvingKey<Self>, SynthesisError> {
+        Groth16::generate_random_parameters_with_reduction(circuit, rng)
+    }
+
+    fn create_proof(
+        circuit: CircomCircuit<Self>,
+        pk: &ProvingKey<Self>,
+        rng: &mut impl Rng,
+    ) -> Result<Proof<Self>, SynthesisError> {
+        Groth16::create_random_proof_with_reduction(circuit, pk, rng)
+    }
+}
+
+impl Engine for Bls12_381 {
+    fn generate_parameters(
+        circuit: CircomCircuit<Self>,
+        rng: &mut impl Rng,
+    ) -> Result<ProvingKey<Self>, SynthesisError> {
+        ark_groth16::Groth16::<Self>::generate_random_parameters_with_reduction(circuit, rng)
+    }
 
-pub fn setup(builder: &CircomBuilder<Bn254>) -> Result<ProvingKey<Bn254>, String> {
+    fn create_proof(
+        circuit: CircomCircuit<Self>,
+        pk: &ProvingKey<Self>,
+        rng: &mut impl Rng,
+    ) -> Result<Proof<Self>, SynthesisError> {
+        ark_groth16::Groth16::<Self>::create_random_proof_with_reduction(circuit, pk, rng)
+    }
+}
+
+pub fn setup<E: Engine>(builder: &CircomBuilder<E>) -> Result<ProvingKey<E>, String> {
     let circuit = builder.setup();
 
     let mut rng = thread_rng();
-    Groth16::generate_random_parameters_with_reduction(circuit, &mut rng)
+    E::generate_parameters(circuit, &mut rng)
         .map_err(|e| format!("Cannot generate params: {:?}", e))
 }
 
-pub fn prove(
-    pk: &ProvingKey<Bn254>,
-    circom: &CircomBuilder<Bn254>,
+pub fn prove<E: Engine>(
+    pk: &ProvingKey<E>,
+    circom: &CircomBuilder<E>,
     input: impl ProveInput,
-) -> Result<Proof<Bn254>, String> {
+) -> Result<Proof<E>, String> {
+    input.check()?;
     let mut circom = circom.clone();
     circom.inputs = input.to_prove_input();
 
@@ -33,17 +91,82 @@ pub fn prove(
 
     // debug_vc_public_inputs(&circuit);
 
-    Groth16::create_random_proof_with_reduction(circuit, pk, &mut thread_rng())
-        .map_err(|e| format!("Cannot prove: {:?}", e))
+    E::create_proof(circuit, pk, &mut thread_rng()).map_err(|e| format!("Cannot prove: {:?}", e))
 }
 
-pub fn verify(
-    vk: &PreparedVerifyingKey<Bn254>,
-    proof: &Proof<Bn254>,
-    public_inputs: &impl VerifyInput,
-) -> Result<bool, String> {
-    Groth16::verify_proof(&vk, proof, &public_inputs.to_verify_input())
-        .map_err(|e| format!("Cannot verify: {:?}", e))
+/// Check that the key has the shape arkworks derives from `r1cs`, so a key for
+/// another circuit is rejected instead of producing proofs that never verify.
+pub fn check_proving_key<E: Pairing>(pk: &ProvingKey<E>, r1cs: &R1CS<E>) -> Result<(), String> {
+    let num_inputs = r1cs.num_inputs;
+    let num_variables = r1cs.num_variables;
+    let queries = [
+        ("vk.gamma_abc_g1", pk.vk.gamma_abc_g1.len(), num_inputs),
+        ("a_query", pk.a_query.len(), num_variables),
+        ("b_g1_query", pk.b_g1_query.len(), num_variables),
+        ("b_g2_query", pk.b_g2_query.len(), num_variables),
+        ("h_query", pk.h_query.len(), domain_size(r1cs) - 1),
+        ("l_query", pk.l_query.len(), num_variables - num_inputs),
+    ];
+    for (query, actual, expected) in queries {
+        if actual != expected {
+            return Err(format!(
+                "Proving key does not match the circuit: {} has {} elements, expected {}",
+                query, actual, expected
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// A proving key paired with the circuit it was checked against.
+pub struct Prover<E: Engine = Bn254> {
+    pk: ProvingKey<E>,
+    circom: CircomBuilder<E>,
+}
+
+impl<E: Engine> Prover<E> {
+    pub fn new(pk: ProvingKey<E>, circom: CircomBuilder<E>) -> Result<Self, String> {
+        check_proving_key(&pk, &circom.cfg.r1cs)?;
+        Ok(Self { pk, circom })
+    }
+
+    /// Load `{name}.pk` for `circom`, which must be built from `{name}.r1cs`;
+    /// the key's stored r1cs fingerprint is checked against that file.
+    pub fn load<const CHECK: bool>(
+        current_dir: &PathBuf,
+        name: &str,
+        circom: CircomBuilder<E>,
+    ) -> Result<Self, String> {
+        let file = open_proving_key::<E, CHECK>(current_dir, name)
+            .map_err(|e| format!("Cannot load proving key: {:?}", e))?;
+        Self::new(file.key, circom)
+    }
+
+    pub fn pk(&self) -> &ProvingKey<E> {
+        &self.pk
+    }
+
+    pub fn prove(&self, input: impl ProveInput) -> Result<Proof<E>, String> {
+        prove(&self.pk, &self.circom, input)
+    }
+}
+
+/// Compute the full witness (in r1cs wire order) without proving, e.g. to
+/// export it with [`crate::snarkjs::write_witness`].
+pub fn calculate_witness<E: Pairing>(
+    circom: &CircomBuilder<E>,
+    input: impl ProveInput,
+) -> Result<Vec<E::ScalarField>, String> {
+    input.check()?;
+    let mut circom = circom.clone();
+    circom.inputs = input.to_prove_input();
+
+    let circuit = circom
+        .build()
+        .map_err(|e| format!("Cannot build circuit: {:?}", e))?;
+    circuit
+        .witness
+        .ok_or_else(|| "Witness is not computed".to_string())
 }
 
 #[allow(dead_code)]
@@ -88,3 +211,131 @@ pub fn debug_vc_public_inputs(circuit: &CircomCircuit<Bn254>) {
     println!("paddedleaf: {}", pull_bytes(&mut slice, 256));
     println!("");
 }
+
+#[cfg(test)]
+pub(crate) mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{aes::encrypt, circuit::circom_builder, types::ByteArray, Signal};
+    use ark_ff::One;
+    use ark_groth16::prepare_verifying_key;
+
+    // x * x = y with public y: wires are [1, y, x].
+    pub(crate) fn square_r1cs<E: Pairing>() -> R1CS<E> {
+        let one = E::ScalarField::one;
+        R1CS {
+            num_inputs: 2,
+            num_aux: 1,
+            num_variables: 3,
+            constraints: vec![(vec![(2, one())], vec![(2, one())], vec![(1, one())])],
+            wire_mapping: None,
+        }
+    }
+
+    fn check_proving_key_shape<E: Engine>() {
+        let circuit = CircomCircuit {
+            r1cs: square_r1cs(),
+            witness: None,
+        };
+        let pk = E::generate_parameters(circuit, &mut ark_std::test_rng()).unwrap();
+        check_proving_key(&pk, &square_r1cs()).unwrap();
+
+        let mut more_inputs = square_r1cs();
+        more_inputs.num_inputs += 1;
+        more_inputs.num_aux -= 1;
+        assert!(check_proving_key(&pk, &more_inputs).is_err());
+
+        let mut more_constraints = square_r1cs();
+        for _ in 0..4 {
+            more_constraints
+                .constraints
+                .push(more_constraints.constraints[0].clone());
+        }
+        assert!(check_proving_key(&pk, &more_constraints).is_err());
+    }
+
+    #[test]
+    fn proving_key_shape_matches_circuit() {
+        check_proving_key_shape::<Bn254>();
+        check_proving_key_shape::<Bls12_381>();
+    }
+
+    /// Setup, key files, prove and verify on a circuit exposing `N` public
+    /// bytes, so every curve is covered without compiled circuits.
+    fn prove_public_bytes<E: Engine, const N: usize>() {
+        use crate::params::{
+            read_proving_key, read_verifying_key, write_proving_key, write_verifying_key,
+            CircuitInfo,
+        };
+
+        let one = E::ScalarField::one;
+        // Wires are [1, b_1, .., b_N, w]: each b_i * 1 = b_i, and w * 1 = b_1.
+        let mut constraints: Vec<_> = (1..=N)
+            .map(|i| (vec![(i, one())], vec![(0, one())], vec![(i, one())]))
+            .collect();
+        constraints.push((vec![(N + 1, one())], vec![(0, one())], vec![(1, one())]));
+        let r1cs = R1CS::<E> {
+            num_inputs: N + 1,
+            num_aux: 1,
+            num_variables: N + 2,
+            constraints,
+            wire_mapping: None,
+        };
+
+        let mut rng = ark_std::test_rng();
+        let circuit = CircomCircuit {
+            r1cs: r1cs.clone(),
+            witness: None,
+        };
+        let pk = E::generate_parameters(circuit, &mut rng).unwrap();
+        let mut raw = vec![];
+        write_proving_key(&pk, &CircuitInfo::default(), &[], &mut raw).unwrap();
+        let pk = read_proving_key::<E, true>(&raw[..]).unwrap().key;
+        let mut raw = vec![];
+        write_verifying_key(&pk.vk, &CircuitInfo::default(), &mut raw).unwrap();
+        let (_, vk) = read_verifying_key::<E>(&raw[..]).unwrap();
+
+        let bytes = ByteArray::new([42u8; N]);
+        let mut witness = vec![one()];
+        witness.extend(bytes.to_signal_fr::<E::ScalarField>());
+        witness.push(witness[1]);
+        let circuit = CircomCircuit {
+            r1cs,
+            witness: Some(witness),
+        };
+        let proof = E::create_proof(circuit, &pk, &mut rng).unwrap();
+        assert!(verify(&vk, &proof, &bytes).unwrap());
+        assert!(!verify(&vk, &proof, &ByteArray::new([43u8; N])).unwrap());
+    }
+
+    #[test]
+    fn public_bytes_on_both_curves() {
+        prove_public_bytes::<Bn254, 16>();
+        prove_public_bytes::<Bls12_381, 16>();
+    }
+
+    fn prove_test_aes<E: Engine>(dir: &PathBuf) {
+        let (key, iv, plaintext) = (*b"verysecretkey123", [7u8; 16], [42u8; 111]);
+        let mut input = HashMap::new();
+        input.insert("plainText", ByteArray::new(plaintext).to_signal());
+        input.insert("iv", ByteArray::new(iv).to_signal());
+        input.insert("key", ByteArray::new(key).to_signal());
+
+        let circom = circom_builder::<E>(dir, "test_aes");
+        let pk = setup(&circom).unwrap();
+        let proof = prove(&pk, &circom, input).unwrap();
+
+        let vk = prepare_verifying_key(&pk.vk);
+        let ciphertext = ByteArray::new(encrypt(&key, &iv, &plaintext));
+        assert!(verify(&vk, &proof, &ciphertext).unwrap());
+        assert!(!verify(&vk, &proof, &ByteArray::new(plaintext)).unwrap());
+    }
+
+    #[test]
+    #[ignore = "needs the circuits from `./build_circuit.sh test_aes`"]
+    fn aes_circuit_on_both_curves() {
+        prove_test_aes::<Bn254>(&"output".into());
+        prove_test_aes::<Bls12_381>(&"output/bls12381".into());
+    }
+}
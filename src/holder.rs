@@ -0,0 +1,236 @@
+//! Holder keys on Baby Jubjub, the curve of circomlib's `babyjub.circom`. A VC
+//! names the public key of its holder, and the circuit checks the holder's
+//! secret key against it, so presenting a VC takes more than its AES key.
+
+use std::str::FromStr;
+
+use ark_bn254::Fr;
+use ark_ff::{Field, PrimeField};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use keccak_hash::keccak;
+use num_bigint::{BigInt as CircomBigInt, BigUint, RandBigInt};
+use rand::Rng;
+use serde::{Deserialize, Serialize, Serializer};
+use zeroize::Zeroize;
+
+use crate::{
+    types::{check_exposed, ByteArray},
+    Signal,
+};
+
+/// Twisted Edwards coefficients `a x^2 + y^2 = 1 + d x^2 y^2`.
+const A: u64 = 168700;
+const D: u64 = 168696;
+
+/// The generator of the prime order subgroup, `Base8` in circomlib.
+fn base8() -> (Fr, Fr) {
+    (
+        Fr::from_str(
+            "5299619240641551281634865583518297030282874472190772894086521144482721001553",
+        )
+        .unwrap(),
+        Fr::from_str(
+            "16950150798460657717958625567821834550301663161624707787222815936182638968203",
+        )
+        .unwrap(),
+    )
+}
+
+/// The order of the subgroup generated by `Base8`.
+fn subgroup_order() -> BigUint {
+    BigUint::from_str(
+        "2736030358979909402780800718157159386076813972158567259200215660948447373041",
+    )
+    .unwrap()
+}
+
+fn is_on_curve((x, y): (Fr, Fr)) -> bool {
+    let (xx, yy) = (x.square(), y.square());
+    Fr::from(A) * xx + yy == Fr::ONE + Fr::from(D) * xx * yy
+}
+
+fn add((x1, y1): (Fr, Fr), (x2, y2): (Fr, Fr)) -> (Fr, Fr) {
+    let k = Fr::from(D) * x1 * x2 * y1 * y2;
+    let x = (x1 * y2 + y1 * x2) * (Fr::ONE + k).inverse().unwrap();
+    let y = (y1 * y2 - Fr::from(A) * x1 * x2) * (Fr::ONE - k).inverse().unwrap();
+    (x, y)
+}
+
+fn mul(point: (Fr, Fr), scalar: &BigUint) -> (Fr, Fr) {
+    let mut acc = (Fr::ZERO, Fr::ONE);
+    for i in (0..scalar.bits()).rev() {
+        acc = add(acc, acc);
+        if scalar.bit(i) {
+            acc = add(acc, point);
+        }
+    }
+    acc
+}
+
+fn fr_to_bytes(x: &Fr) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    x.serialize_compressed(&mut bytes[..]).unwrap();
+    bytes
+}
+
+fn fr_from_bytes(bytes: &[u8]) -> Result<Fr, String> {
+    Fr::deserialize_compressed(bytes).map_err(|_| "Not a canonical field element".to_string())
+}
+
+/// A holder secret key, a scalar below the subgroup order, hex encoded as
+/// 32 little endian bytes. Like a [`crate::types::Secret`], it serializes
+/// only inside [`crate::types::expose_secrets`].
+#[derive(Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "ByteArray<32>")]
+pub struct HolderKey(Fr);
+
+impl HolderKey {
+    pub fn random<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        let scalar = rng.gen_biguint_below(&subgroup_order());
+        HolderKey(Fr::from(scalar))
+    }
+
+    /// Derive a key from high-entropy seed material, e.g. a wallet seed, so the
+    /// holder can recreate it instead of storing it.
+    pub fn from_seed(seed: &[u8]) -> Self {
+        let mut material = b"vc-prove holder key".to_vec();
+        material.extend_from_slice(seed);
+        let digest = BigUint::from_bytes_le(keccak(material).as_bytes());
+        HolderKey(Fr::from(digest % subgroup_order()))
+    }
+
+    pub fn public_key(&self) -> HolderPublicKey {
+        let (x, y) = mul(base8(), &self.0.into_bigint().into());
+        HolderPublicKey { x, y }
+    }
+}
+
+impl Zeroize for HolderKey {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl TryFrom<ByteArray<32>> for HolderKey {
+    type Error = String;
+
+    fn try_from(value: ByteArray<32>) -> Result<Self, Self::Error> {
+        let bytes: &[u8] = value.as_ref();
+        if BigUint::from_bytes_le(bytes) >= subgroup_order() {
+            return Err("Holder key is not below the subgroup order".to_string());
+        }
+        Ok(HolderKey(fr_from_bytes(bytes)?))
+    }
+}
+
+impl From<HolderKey> for ByteArray<32> {
+    fn from(value: HolderKey) -> Self {
+        ByteArray::new(fr_to_bytes(&value.0))
+    }
+}
+
+impl Serialize for HolderKey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        check_exposed()?;
+        ByteArray::new(fr_to_bytes(&self.0)).serialize(serializer)
+    }
+}
+
+impl Signal for HolderKey {
+    fn to_signal(&self) -> Vec<CircomBigInt> {
+        vec![BigUint::from(self.0.into_bigint()).into()]
+    }
+}
+
+/// A point of the Baby Jubjub subgroup, hex encoded as the little endian
+/// coordinates `x || y`, which is also how it is stored in an encoded VC.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "ByteArray<64>", into = "ByteArray<64>")]
+pub struct HolderPublicKey {
+    x: Fr,
+    y: Fr,
+}
+
+impl HolderPublicKey {
+    pub fn to_bytes(&self) -> [u8; 64] {
+        let mut bytes = [0u8; 64];
+        bytes[..32].copy_from_slice(&fr_to_bytes(&self.x));
+        bytes[32..].copy_from_slice(&fr_to_bytes(&self.y));
+        bytes
+    }
+}
+
+impl TryFrom<ByteArray<64>> for HolderPublicKey {
+    type Error = String;
+
+    fn try_from(value: ByteArray<64>) -> Result<Self, Self::Error> {
+        let bytes: &[u8] = value.as_ref();
+        let point = (fr_from_bytes(&bytes[..32])?, fr_from_bytes(&bytes[32..])?);
+        if !is_on_curve(point) || mul(point, &subgroup_order()) != (Fr::ZERO, Fr::ONE) {
+            return Err("Holder public key is not in the Baby Jubjub subgroup".to_string());
+        }
+        Ok(HolderPublicKey {
+            x: point.0,
+            y: point.1,
+        })
+    }
+}
+
+impl From<HolderPublicKey> for ByteArray<64> {
+    fn from(value: HolderPublicKey) -> Self {
+        ByteArray::new(value.to_bytes())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::types::{expose_secrets, Secret};
+    use rand::thread_rng;
+
+    #[test]
+    fn base8_generates_the_subgroup() {
+        assert!(is_on_curve(base8()));
+        assert_eq!(mul(base8(), &subgroup_order()), (Fr::ZERO, Fr::ONE));
+        assert_eq!(mul(base8(), &BigUint::from(1u8)), base8());
+        assert_eq!(
+            mul(base8(), &BigUint::from(3u8)),
+            add(base8(), add(base8(), base8()))
+        );
+    }
+
+    #[test]
+    fn holder_key_roundtrip() {
+        let key = HolderKey::random(&mut thread_rng());
+        let public_key = key.public_key();
+        assert!(is_on_curve((public_key.x, public_key.y)));
+
+        assert!(serde_json::to_string(&key).is_err());
+        let key = Secret::new(key);
+        let json = expose_secrets(|| serde_json::to_string(&key)).unwrap();
+        let decoded: HolderKey = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.public_key(), public_key);
+
+        let json = serde_json::to_string(&public_key).unwrap();
+        assert_eq!(json.len(), 2 + 128);
+        assert_eq!(
+            serde_json::from_str::<HolderPublicKey>(&json).unwrap(),
+            public_key
+        );
+
+        let mut bytes = public_key.to_bytes();
+        bytes[0] ^= 1;
+        let tampered = serde_json::to_string(&hex::encode(bytes)).unwrap();
+        assert!(serde_json::from_str::<HolderPublicKey>(&tampered).is_err());
+
+        let too_large = serde_json::to_string(&"ff".repeat(32)).unwrap();
+        assert!(serde_json::from_str::<HolderKey>(&too_large).is_err());
+    }
+
+    #[test]
+    fn holder_key_from_seed() {
+        let key = HolderKey::from_seed(b"wallet seed");
+        assert!(key == HolderKey::from_seed(b"wallet seed"));
+        assert!(key != HolderKey::from_seed(b"another seed"));
+    }
+}
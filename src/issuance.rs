@@ -0,0 +1,365 @@
+//! Issuance of VCs: encrypt a [`VC`] under a fresh key and IV into the leaf
+//! bytes uploaded to the storage flow, and bundle the secrets the holder needs
+//! to prove it later.
+//!
+//! A key and IV pair must never encrypt two leaves: in counter mode the XOR of
+//! the ciphertexts reveals the XOR of the VCs. Every pair goes through an
+//! [`IvRegistry`], which remembers a fingerprint of each pair it has seen.
+
+use std::{
+    collections::HashSet,
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::PathBuf,
+};
+
+use chrono::NaiveDate;
+use keccak_hash::{keccak, H256};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroize;
+
+use crate::{
+    aes::{derive_key_iv, AesKey, AesMode},
+    types::{expose_secrets, ByteArray, HashMode, Secret, VcProveInput, VC},
+};
+
+/// The key and IV pairs used so far, as `keccak(key || iv)` so the record
+/// does not hold keys. With a file, each pair is appended as a hex line before
+/// the leaf is returned, so a crash never forgets an issued pair. The file
+/// must not be shared by concurrent issuers.
+#[derive(Default)]
+pub struct IvRegistry {
+    file: Option<File>,
+    used: HashSet<H256>,
+}
+
+impl IvRegistry {
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// Open the record at `path`, creating it if missing.
+    pub fn open(path: &PathBuf) -> Result<Self, String> {
+        let file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("Cannot open IV registry: {}", e))?;
+
+        let mut used = HashSet::new();
+        for line in BufReader::new(&file).lines() {
+            let line = line.map_err(|e| format!("Cannot read IV registry: {}", e))?;
+            if line.is_empty() {
+                continue;
+            }
+            let bytes = hex::decode(&line)
+                .ok()
+                .filter(|x| x.len() == 32)
+                .ok_or(format!("Corrupted IV registry line: {}", line))?;
+            used.insert(H256::from_slice(&bytes));
+        }
+        Ok(Self {
+            file: Some(file),
+            used,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.used.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.used.is_empty()
+    }
+
+    fn fingerprint(key: &AesKey, iv: &ByteArray<16>) -> H256 {
+        let mut material = key.bytes().to_vec();
+        material.extend_from_slice(iv.as_ref());
+        keccak(material)
+    }
+
+    pub fn contains(&self, key: &AesKey, iv: &ByteArray<16>) -> bool {
+        self.used.contains(&Self::fingerprint(key, iv))
+    }
+
+    /// Record a pair, failing if it was used before.
+    pub fn register(&mut self, key: &AesKey, iv: &ByteArray<16>) -> Result<(), String> {
+        let fingerprint = Self::fingerprint(key, iv);
+        if self.used.contains(&fingerprint) {
+            return Err("Key and IV were already used for another leaf".to_string());
+        }
+        if let Some(file) = &mut self.file {
+            writeln!(file, "{}", hex::encode(fingerprint))
+                .and_then(|_| file.sync_data())
+                .map_err(|e| format!("Cannot write IV registry: {}", e))?;
+        }
+        self.used.insert(fingerprint);
+        Ok(())
+    }
+}
+
+/// Everything the holder needs besides the merkle proof, delivered privately.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct HolderBundle {
+    pub key: Secret<AesKey>,
+    pub iv: Secret<ByteArray<16>>,
+    #[serde(default)]
+    pub aes_mode: AesMode,
+    pub vc: Secret<VC>,
+}
+
+impl HolderBundle {
+    /// The prove input once the leaf is stored and its merkle proof known.
+    /// Fails for a GCM IV whose last 4 bytes are not zero.
+    pub fn to_prove_input(
+        &self,
+        birthdate_threshold: NaiveDate,
+        merkle_proof: Vec<H256>,
+        path_index: usize,
+    ) -> Result<VcProveInput, String> {
+        VcProveInput::new(
+            (*self.vc).clone(),
+            (*self.key).clone(),
+            (*self.iv).clone(),
+            birthdate_threshold,
+            merkle_proof,
+            path_index,
+        )
+        .with_aes_mode(self.aes_mode)
+    }
+
+    /// The stored bytes, [`VC::chunks`] leaves of `CHUNK_LEN` bytes.
+    pub fn leaf(&self) -> Result<Vec<u8>, String> {
+        // The leaf does not depend on the extensions or the merkle proof.
+        Ok(self.to_prove_input(NaiveDate::MIN, vec![], 0)?.leaf())
+    }
+
+    pub fn leaf_hash(&self, hash_mode: HashMode) -> Result<H256, String> {
+        Ok(self
+            .to_prove_input(NaiveDate::MIN, vec![], 0)?
+            .with_hash_mode(hash_mode)
+            .leaf_hash())
+    }
+}
+
+/// An issued VC: the leaf for upload and the holder's secrets.
+pub struct Issued {
+    pub leaf: Vec<u8>,
+    pub bundle: HolderBundle,
+}
+
+impl Issued {
+    /// Write `<name>.leaf` for upload and `<name>.holder.json` for delivery,
+    /// which holds the secrets in the clear and on unix is readable by the
+    /// owner only (mode 0600).
+    pub fn save(&self, dir: &PathBuf, name: &str) -> Result<(), String> {
+        let file_name = format!("{}.leaf", name);
+        std::fs::write(dir.join(&file_name), &self.leaf)
+            .map_err(|e| format!("Cannot write {}: {}", file_name, e))?;
+
+        let file_name = format!("{}.holder.json", name);
+        let mut json = expose_secrets(|| serde_json::to_vec_pretty(&self.bundle)).unwrap();
+        let result = create_private(&dir.join(&file_name)).and_then(|mut x| x.write_all(&json));
+        json.zeroize();
+        result.map_err(|e| format!("Cannot write {}: {}", file_name, e))
+    }
+}
+
+fn create_private(path: &PathBuf) -> std::io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        // The mode only applies to a new file, so narrow an existing one too.
+        let file = options.mode(0o600).open(path)?;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+        Ok(file)
+    }
+    #[cfg(not(unix))]
+    options.open(path)
+}
+
+pub struct Issuer {
+    registry: IvRegistry,
+    aes256: bool,
+    aes_mode: AesMode,
+}
+
+impl Issuer {
+    /// Issue with AES-128-CTR, the cipher of `circuits/check_vc.circom`.
+    pub fn new(registry: IvRegistry) -> Self {
+        Self {
+            registry,
+            aes256: false,
+            aes_mode: AesMode::Ctr,
+        }
+    }
+
+    /// The circuit must be compiled for the same cipher, see `AesNk`.
+    pub fn with_cipher(mut self, aes256: bool, aes_mode: AesMode) -> Self {
+        self.aes256 = aes256;
+        self.aes_mode = aes_mode;
+        self
+    }
+
+    pub fn registry(&self) -> &IvRegistry {
+        &self.registry
+    }
+
+    /// Encrypt `vc` under a fresh random key and IV.
+    pub fn issue<R: Rng + ?Sized>(&mut self, rng: &mut R, vc: VC) -> Result<Issued, String> {
+        let key: AesKey = if self.aes256 {
+            ByteArray::new(rng.gen::<[u8; 32]>()).into()
+        } else {
+            ByteArray::new(rng.gen::<[u8; 16]>()).into()
+        };
+        let mut iv: [u8; 16] = rng.gen();
+        if self.aes_mode == AesMode::Gcm {
+            iv[12..].fill(0);
+        }
+        self.issue_with(key, ByteArray::new(iv), vc)
+    }
+
+    /// Encrypt `vc` under the key and IV derived from the holder's master
+    /// secret and its serial number, see [`derive_key_iv`]. Reissuing a serial
+    /// number fails, since it would reuse the pair.
+    pub fn issue_derived(
+        &mut self,
+        master_secret: &ByteArray<32>,
+        vc: VC,
+    ) -> Result<Issued, String> {
+        if self.aes256 || self.aes_mode != AesMode::Ctr {
+            return Err("Derived keys are for AES-128-CTR".to_string());
+        }
+        let (key, iv) = derive_key_iv(master_secret, vc.serial_no());
+        self.issue_with(key.into(), iv, vc)
+    }
+
+    /// Encrypt `vc` under a given key and IV, e.g. from a key management
+    /// system, failing if the pair was used before.
+    pub fn issue_with(&mut self, key: AesKey, iv: ByteArray<16>, vc: VC) -> Result<Issued, String> {
+        self.aes_mode.check_iv(iv.as_ref())?;
+        self.registry.register(&key, &iv)?;
+        let bundle = HolderBundle {
+            key: key.into(),
+            iv: iv.into(),
+            aes_mode: self.aes_mode,
+            vc: vc.into(),
+        };
+        Ok(Issued {
+            leaf: bundle.leaf()?,
+            bundle,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        aes::{decrypt_leaf, LeafCipher},
+        sample::Sample,
+        types::{CHUNK_LEN, LEAF_CIPHER_OFFSET},
+    };
+    use rand::thread_rng;
+
+    #[test]
+    fn issue_leaf() {
+        let mut issuer = Issuer::new(IvRegistry::in_memory());
+        let issued = issuer.issue(&mut thread_rng(), Sample::vc()).unwrap();
+        assert_eq!(issued.leaf.len(), CHUNK_LEN);
+        assert_eq!(issued.leaf[LEAF_CIPHER_OFFSET], LeafCipher::Aes128Ctr.id());
+
+        let bundle = &issued.bundle;
+        let plaintext = Sample::vc().plaintext();
+        let decrypted = decrypt_leaf(
+            &bundle.key,
+            bundle.aes_mode,
+            bundle.iv.as_ref(),
+            &issued.leaf[16..16 + plaintext.len()],
+            &[0u8; 16],
+        )
+        .unwrap();
+        assert_eq!(decrypted, plaintext);
+
+        assert!(serde_json::to_string(bundle).is_err());
+        let json = expose_secrets(|| serde_json::to_string(bundle)).unwrap();
+        let bundle: HolderBundle = serde_json::from_str(&json).unwrap();
+        let input = bundle
+            .to_prove_input(Sample::threshold(), Sample::merkle_path(3), 0)
+            .unwrap();
+        assert_eq!(input.leaf(), issued.leaf);
+
+        let mut issuer = Issuer::new(IvRegistry::in_memory()).with_cipher(true, AesMode::Gcm);
+        let issued = issuer.issue(&mut thread_rng(), Sample::vc()).unwrap();
+        assert_eq!(issued.leaf[LEAF_CIPHER_OFFSET], LeafCipher::Aes256Gcm.id());
+        assert_eq!(
+            AsRef::<[u8; 16]>::as_ref(&*issued.bundle.iv)[12..],
+            [0u8; 4]
+        );
+    }
+
+    #[test]
+    fn save_holder_bundle() {
+        let dir = std::env::temp_dir().join(format!("issued-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut issuer = Issuer::new(IvRegistry::in_memory());
+        let issued = issuer.issue(&mut thread_rng(), Sample::vc()).unwrap();
+        issued.save(&dir, "alice").unwrap();
+
+        assert_eq!(std::fs::read(dir.join("alice.leaf")).unwrap(), issued.leaf);
+        let raw = std::fs::read(dir.join("alice.holder.json")).unwrap();
+        let bundle: HolderBundle = serde_json::from_slice(&raw).unwrap();
+        assert_eq!(bundle.leaf().unwrap(), issued.leaf);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let metadata = std::fs::metadata(dir.join("alice.holder.json")).unwrap();
+            assert_eq!(metadata.permissions().mode() & 0o777, 0o600);
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn key_iv_reuse_is_rejected() {
+        let path = std::env::temp_dir().join(format!("iv-registry-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let key: AesKey = ByteArray::new(*b"verysecretkey123").into();
+        let iv = ByteArray::new(*b"uniqueiv12345678");
+
+        let mut issuer = Issuer::new(IvRegistry::open(&path).unwrap());
+        issuer
+            .issue_with(key.clone(), iv.clone(), Sample::vc())
+            .unwrap();
+        assert!(issuer
+            .issue_with(key.clone(), iv.clone(), Sample::vc())
+            .is_err());
+        drop(issuer);
+
+        // The record survives a restart.
+        let mut registry = IvRegistry::open(&path).unwrap();
+        assert_eq!(registry.len(), 1);
+        assert!(registry.contains(&key, &iv));
+        assert!(registry.register(&key, &iv).is_err());
+        registry
+            .register(&key, &ByteArray::new(*b"uniqueiv12345679"))
+            .unwrap();
+        assert_eq!(IvRegistry::open(&path).unwrap().len(), 2);
+
+        let mut issuer = Issuer::new(IvRegistry::in_memory());
+        issuer
+            .issue_derived(&Sample::master_secret(), Sample::vc())
+            .unwrap();
+        assert!(issuer
+            .issue_derived(&Sample::master_secret(), Sample::vc())
+            .is_err());
+
+        let mut issuer = Issuer::new(IvRegistry::in_memory()).with_cipher(false, AesMode::Gcm);
+        assert!(issuer.issue_with(key, iv, Sample::vc()).is_err());
+        assert!(issuer.registry().is_empty());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
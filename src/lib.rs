@@ -1,15 +1,42 @@
+#[cfg(feature = "prover")]
 pub mod aes;
+#[cfg(feature = "prover")]
 pub mod args;
+#[cfg(feature = "prover")]
+pub mod backend;
+#[cfg(feature = "prover")]
 pub mod circuit;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "prover")]
 pub mod groth16;
+pub mod holder;
+#[cfg(feature = "prover")]
+pub mod issuance;
 #[cfg(feature = "libsnark")]
 pub mod libsnark;
 pub mod params;
+#[cfg(feature = "prover")]
+pub mod phase2;
+pub mod policy;
+pub mod presentation;
+#[cfg(feature = "python")]
+mod python;
+pub mod roots;
+#[cfg(feature = "prover")]
 pub mod sample;
 mod signal;
+#[cfg(feature = "prover")]
+pub mod snarkjs;
 pub mod types;
 mod utils;
+pub mod verifier;
+#[cfg(feature = "prover")]
+pub mod wallet;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
+#[cfg(feature = "prover")]
 pub use args::{get_zk_task_input, get_zk_task_name};
 pub use signal::Signal;
 
@@ -17,3 +44,12 @@ pub fn warmup_current_thread() {
     #[cfg(feature = "cuda")]
     ark_groth16::init_local_workspace();
 }
+
+/// Resident set size of the current process in bytes, read from procfs.
+/// Returns `None` where `/proc/self/status` is unavailable.
+pub fn resident_memory() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kb * 1024)
+}
@@ -1,7 +1,13 @@
-use ark_bn254::Bn254;
+use std::{any::Any, rc::Rc, sync::Once};
+
+use ark_bn254::{Bn254, Fr};
 use ark_circom::CircomCircuit;
 
+use libsnark_rust::snark;
 use libsnark_rust::types::{ConstraintSystem, Input, LinearCombination};
+use libsnark_rust::utils::init_public_params;
+
+use crate::backend::ProvingBackend;
 
 pub fn make_constraints(circuit: &CircomCircuit<Bn254>) -> ConstraintSystem {
     let mut cs = ConstraintSystem::new(circuit.r1cs.num_inputs - 1, circuit.r1cs.num_aux);
@@ -36,3 +42,124 @@ pub fn make_input(circuit: &CircomCircuit<Bn254>) -> Option<(Input, Input)> {
 
     Some((primary_inputs, auxiliary_inputs))
 }
+
+/// The libsnark `r1cs_ppzksnark` prover over BN254.
+pub struct Libsnark;
+
+/// A libsnark keypair. libsnark-rust keeps keys and proofs as opaque C++
+/// objects, so this holds only what `snark::prove` and `snark::verify` need,
+/// with the types inferred from those functions as in `bin/libsnark.rs`.
+pub struct LibsnarkKey {
+    prove: Box<ProveFn>,
+    verify: Box<VerifyFn>,
+}
+
+type ProveFn = dyn Fn(&Input, &Input) -> LibsnarkProof;
+type VerifyFn = dyn Fn(&Input, &LibsnarkProof) -> Result<bool, String>;
+
+pub struct LibsnarkProof(Box<dyn Any>);
+
+impl LibsnarkKey {
+    fn new<K: 'static, P: 'static>(
+        key: K,
+        prove: fn(&K, &Input, &Input) -> P,
+        verify: fn(&K, &Input, &P) -> bool,
+    ) -> Self {
+        let key = Rc::new(key);
+        let prover_key = key.clone();
+        Self {
+            prove: Box::new(move |primary_input, auxiliary_input| {
+                LibsnarkProof(Box::new(prove(&prover_key, primary_input, auxiliary_input)))
+            }),
+            verify: Box::new(move |primary_input, proof| {
+                let proof = proof
+                    .0
+                    .downcast_ref::<P>()
+                    .ok_or("Proof is from another libsnark key type")?;
+                Ok(verify(&key, primary_input, proof))
+            }),
+        }
+    }
+}
+
+fn init() {
+    static INIT: Once = Once::new();
+    INIT.call_once(init_public_params);
+}
+
+fn witness_inputs(circuit: &CircomCircuit<Bn254>) -> Result<(Input, Input), String> {
+    make_input(circuit).ok_or_else(|| "Witness is not computed".to_string())
+}
+
+impl ProvingBackend for Libsnark {
+    type ProvingKey = LibsnarkKey;
+    type Proof = LibsnarkProof;
+
+    fn is_satisfied(circuit: CircomCircuit<Bn254>) -> Result<bool, String> {
+        init();
+        let (primary_input, auxiliary_input) = witness_inputs(&circuit)?;
+        Ok(make_constraints(&circuit).is_satisfied(&primary_input, &auxiliary_input))
+    }
+
+    fn setup(circuit: CircomCircuit<Bn254>) -> Result<Self::ProvingKey, String> {
+        init();
+        let key = snark::setup(&make_constraints(&circuit));
+        Ok(LibsnarkKey::new(key, snark::prove, snark::verify))
+    }
+
+    fn prove(pk: &Self::ProvingKey, circuit: CircomCircuit<Bn254>) -> Result<Self::Proof, String> {
+        init();
+        let (primary_input, auxiliary_input) = witness_inputs(&circuit)?;
+        Ok((pk.prove)(&primary_input, &auxiliary_input))
+    }
+
+    fn verify(
+        pk: &Self::ProvingKey,
+        public_inputs: &[Fr],
+        proof: &Self::Proof,
+    ) -> Result<bool, String> {
+        init();
+        (pk.verify)(&Input::from_fr(public_inputs), proof)
+    }
+
+    // libsnark-rust has no binding for libsnark's proof stream operators.
+    fn serialize_proof(_proof: &Self::Proof) -> Result<Vec<u8>, String> {
+        Err("libsnark proofs cannot be serialized".to_string())
+    }
+
+    fn deserialize_proof(_raw: &[u8]) -> Result<Self::Proof, String> {
+        Err("libsnark proofs cannot be deserialized".to_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::backend::{
+        test::{check_backend, square_circuit},
+        Arkworks,
+    };
+
+    #[test]
+    fn libsnark_backend() {
+        check_backend::<Libsnark>();
+    }
+
+    #[test]
+    fn same_witness_on_both_backends() {
+        for (x, y, valid) in [(3, 9, true), (3, 10, false)] {
+            assert_eq!(Arkworks::is_satisfied(square_circuit(x, y)).unwrap(), valid);
+            assert_eq!(Libsnark::is_satisfied(square_circuit(x, y)).unwrap(), valid);
+        }
+
+        let public_inputs = square_circuit(3, 9).get_public_inputs().unwrap();
+        let ark_pk = Arkworks::setup(square_circuit(3, 9)).unwrap();
+        let ark_proof = Arkworks::prove(&ark_pk, square_circuit(3, 9)).unwrap();
+        assert!(Arkworks::verify(&ark_pk, &public_inputs, &ark_proof).unwrap());
+
+        let snark_pk = Libsnark::setup(square_circuit(3, 9)).unwrap();
+        let snark_proof = Libsnark::prove(&snark_pk, square_circuit(3, 9)).unwrap();
+        assert!(Libsnark::verify(&snark_pk, &public_inputs, &snark_proof).unwrap());
+        assert!(Libsnark::serialize_proof(&snark_proof).is_err());
+    }
+}
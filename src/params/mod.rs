@@ -0,0 +1,380 @@
+//! On-disk format of the Groth16 keys.
+//!
+//! A key file is a fixed header, the key sections and a trailing digest:
+//!
+//! ```text
+//! magic [8] | version u32 | r1cs hash [32] | constraints u64 | inputs u64 | variables u64
+//! | sections u32 | (offset u64, length u64) * sections | section data ... | digest [32]
+//! ```
+//!
+//! Integers are little endian and points are uncompressed arkworks encodings
+//! on the curve the key was generated for, which is not recorded: a key read
+//! with the wrong engine fails on the section lengths.
+//! The digest is the keccak of the header followed by the digest of every
+//! section, each one a keccak over the keccaks of its 1 MiB chunks so large
+//! sections hash in parallel. The header ties the key to the `.r1cs` file it
+//! was generated from, so a key for another or a rebuilt circuit is rejected
+//! when loaded instead of producing invalid proofs.
+
+use std::{
+    borrow::Cow,
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    marker::PhantomData,
+    path::PathBuf,
+};
+
+use ark_ec::pairing::Pairing;
+use ark_groth16::{prepare_verifying_key, PreparedVerifyingKey, VerifyingKey};
+use keccak_hash::{keccak, H256};
+use tiny_keccak::{Hasher, Keccak};
+
+use rayon::prelude::*;
+
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, SerializationError, Validate,
+};
+
+use crate::utils::invalid_data;
+
+#[cfg(feature = "prover")]
+mod proving;
+
+#[cfg(feature = "prover")]
+pub use proving::{
+    load_proving_key, map_proving_key, open_proving_key, read_proving_key, save_key,
+    write_proving_key, ProvingKeyFile,
+};
+
+#[cfg(all(test, feature = "prover"))]
+pub(crate) use proving::test::random_key;
+
+pub const KEY_FORMAT_VERSION: u32 = 1;
+
+const VK_MAGIC: &[u8; 8] = b"0gvc-vk\0";
+const VK_SECTIONS: usize = 1;
+
+const DIGEST_CHUNK: usize = 1 << 20;
+
+/// The identity of the circuit a key was generated for.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CircuitInfo {
+    /// Keccak-256 of the `.r1cs` file.
+    pub r1cs_hash: H256,
+    pub num_constraints: u64,
+    /// Instance variables, including the constant one.
+    pub num_inputs: u64,
+    pub num_variables: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyHeader {
+    pub version: u32,
+    pub circuit: CircuitInfo,
+    /// `(offset, length)` of each section, offsets counted from the start of the file.
+    pub sections: Vec<(u64, u64)>,
+}
+
+impl KeyHeader {
+    fn new(circuit: CircuitInfo, lengths: &[u64]) -> Self {
+        let mut offset = Self::encoded_len(lengths.len()) as u64;
+        let sections = lengths
+            .iter()
+            .map(|&length| {
+                offset += length;
+                (offset - length, length)
+            })
+            .collect();
+        Self {
+            version: KEY_FORMAT_VERSION,
+            circuit,
+            sections,
+        }
+    }
+
+    fn encoded_len(num_sections: usize) -> usize {
+        8 + 4 + 32 + 3 * 8 + 4 + 16 * num_sections
+    }
+
+    fn encode(&self, magic: &[u8; 8]) -> Vec<u8> {
+        let mut raw = Vec::with_capacity(Self::encoded_len(self.sections.len()));
+        raw.extend_from_slice(magic);
+        raw.extend_from_slice(&self.version.to_le_bytes());
+        raw.extend_from_slice(self.circuit.r1cs_hash.as_bytes());
+        raw.extend_from_slice(&self.circuit.num_constraints.to_le_bytes());
+        raw.extend_from_slice(&self.circuit.num_inputs.to_le_bytes());
+        raw.extend_from_slice(&self.circuit.num_variables.to_le_bytes());
+        raw.extend_from_slice(&(self.sections.len() as u32).to_le_bytes());
+        for (offset, length) in &self.sections {
+            raw.extend_from_slice(&offset.to_le_bytes());
+            raw.extend_from_slice(&length.to_le_bytes());
+        }
+        raw
+    }
+
+    fn decode(
+        mut reader: impl Read,
+        magic: &[u8; 8],
+        num_sections: usize,
+    ) -> Result<(Self, Vec<u8>), SerializationError> {
+        let mut raw = vec![0u8; Self::encoded_len(0)];
+        reader.read_exact(&mut raw)?;
+        if &raw[..8] != magic {
+            return Err(invalid_data(format!(
+                "Invalid magic, expected {:?}, actual {:?}",
+                String::from_utf8_lossy(magic),
+                String::from_utf8_lossy(&raw[..8])
+            )));
+        }
+        let u64_at =
+            |raw: &[u8], pos: usize| u64::from_le_bytes(raw[pos..pos + 8].try_into().unwrap());
+
+        let version = u32::from_le_bytes(raw[8..12].try_into().unwrap());
+        if version != KEY_FORMAT_VERSION {
+            return Err(invalid_data(format!(
+                "Unsupported key format version {}, expected {}",
+                version, KEY_FORMAT_VERSION
+            )));
+        }
+        let circuit = CircuitInfo {
+            r1cs_hash: H256::from_slice(&raw[12..44]),
+            num_constraints: u64_at(&raw, 44),
+            num_inputs: u64_at(&raw, 52),
+            num_variables: u64_at(&raw, 60),
+        };
+        let actual_sections = u32::from_le_bytes(raw[68..72].try_into().unwrap()) as usize;
+        if actual_sections != num_sections {
+            return Err(invalid_data(format!(
+                "Expected {} sections, found {}",
+                num_sections, actual_sections
+            )));
+        }
+
+        let table_start = raw.len();
+        raw.resize(Self::encoded_len(num_sections), 0);
+        reader.read_exact(&mut raw[table_start..])?;
+        let sections: Vec<(u64, u64)> = (0..num_sections)
+            .map(|i| {
+                let pos = table_start + 16 * i;
+                (u64_at(&raw, pos), u64_at(&raw, pos + 8))
+            })
+            .collect();
+
+        let header = Self::new(circuit, &sections.iter().map(|x| x.1).collect::<Vec<_>>());
+        if header.sections != sections {
+            return Err(invalid_data("Section table is not contiguous"));
+        }
+        Ok((header, raw))
+    }
+
+    /// Reject a key that was not generated for `circuit`.
+    pub fn check_circuit(&self, circuit: &CircuitInfo) -> Result<(), SerializationError> {
+        if &self.circuit != circuit {
+            return Err(invalid_data(format!(
+                "Key was generated for circuit {:?} ({} constraints), not {:?} ({} constraints)",
+                self.circuit.r1cs_hash,
+                self.circuit.num_constraints,
+                circuit.r1cs_hash,
+                circuit.num_constraints
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn section_digest(raw: &[u8]) -> H256 {
+    let chunks: Vec<u8> = raw
+        .par_chunks(DIGEST_CHUNK)
+        .flat_map_iter(|chunk| keccak(chunk).0)
+        .collect();
+    keccak(chunks)
+}
+
+struct KeyFileWriter<W> {
+    writer: W,
+    hasher: Keccak,
+    lengths: std::vec::IntoIter<u64>,
+}
+
+impl<W: Write> KeyFileWriter<W> {
+    fn new(
+        mut writer: W,
+        magic: &[u8; 8],
+        circuit: &CircuitInfo,
+        lengths: Vec<u64>,
+    ) -> Result<Self, SerializationError> {
+        let header = KeyHeader::new(*circuit, &lengths).encode(magic);
+        writer.write_all(&header)?;
+        let mut hasher = Keccak::v256();
+        hasher.update(&header);
+        Ok(Self {
+            writer,
+            hasher,
+            lengths: lengths.into_iter(),
+        })
+    }
+
+    fn section(&mut self, raw: &[u8]) -> Result<(), SerializationError> {
+        assert_eq!(self.lengths.next(), Some(raw.len() as u64));
+        self.hasher.update(section_digest(raw).as_bytes());
+        self.writer.write_all(raw)?;
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<(), SerializationError> {
+        assert!(self.lengths.next().is_none());
+        let mut digest = [0u8; 32];
+        self.hasher.finalize(&mut digest);
+        self.writer.write_all(&digest)?;
+        Ok(())
+    }
+}
+
+/// Where the sections of a key file come from: a stream, read section by
+/// section, or a mapped file whose sections are decoded in place.
+trait KeySource<'a>: Read {
+    fn section(&mut self, length: u64) -> Result<Cow<'a, [u8]>, SerializationError>;
+}
+
+struct Stream<R>(R);
+
+impl<R: Read> Read for Stream<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl<'a, R: Read> KeySource<'a> for Stream<R> {
+    fn section(&mut self, length: u64) -> Result<Cow<'a, [u8]>, SerializationError> {
+        let mut raw = vec![];
+        (&mut self.0).take(length).read_to_end(&mut raw)?;
+        if raw.len() as u64 != length {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        Ok(Cow::Owned(raw))
+    }
+}
+
+impl<'a> KeySource<'a> for &'a [u8] {
+    fn section(&mut self, length: u64) -> Result<Cow<'a, [u8]>, SerializationError> {
+        if (self.len() as u64) < length {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        let (section, rest) = self.split_at(length as usize);
+        *self = rest;
+        Ok(Cow::Borrowed(section))
+    }
+}
+
+struct KeyFileReader<'a, S> {
+    source: S,
+    header: KeyHeader,
+    hasher: Keccak,
+    next: usize,
+    _marker: PhantomData<&'a [u8]>,
+}
+
+impl<'a, S: KeySource<'a>> KeyFileReader<'a, S> {
+    fn new(
+        mut source: S,
+        magic: &[u8; 8],
+        num_sections: usize,
+    ) -> Result<Self, SerializationError> {
+        let (header, raw) = KeyHeader::decode(&mut source, magic, num_sections)?;
+        let mut hasher = Keccak::v256();
+        hasher.update(&raw);
+        Ok(Self {
+            source,
+            header,
+            hasher,
+            next: 0,
+            _marker: PhantomData,
+        })
+    }
+
+    fn section(&mut self) -> Result<Cow<'a, [u8]>, SerializationError> {
+        let (_, length) = self.header.sections[self.next];
+        self.next += 1;
+
+        let raw = self.source.section(length)?;
+        self.hasher.update(section_digest(&raw).as_bytes());
+        Ok(raw)
+    }
+
+    fn finish(mut self) -> Result<KeyHeader, SerializationError> {
+        let mut expected = [0u8; 32];
+        self.source.read_exact(&mut expected)?;
+        let mut digest = [0u8; 32];
+        self.hasher.finalize(&mut digest);
+        if digest != expected {
+            return Err(invalid_data("Key digest mismatch, the file is corrupted"));
+        }
+        Ok(self.header)
+    }
+}
+
+fn to_bytes(item: &impl CanonicalSerialize) -> Vec<u8> {
+    let mut raw = Vec::with_capacity(item.uncompressed_size());
+    item.serialize_uncompressed(&mut raw).unwrap();
+    raw
+}
+
+fn from_bytes<T: CanonicalDeserialize, const CHECK: bool>(
+    raw: &[u8],
+) -> Result<T, SerializationError> {
+    let check = if CHECK { Validate::Yes } else { Validate::No };
+    let mut reader = raw;
+    let item = T::deserialize_with_mode(&mut reader, Compress::No, check)?;
+    if !reader.is_empty() {
+        return Err(invalid_data("Trailing bytes in key section"));
+    }
+    Ok(item)
+}
+
+pub fn write_verifying_key<E: Pairing>(
+    key: &VerifyingKey<E>,
+    circuit: &CircuitInfo,
+    writer: impl Write,
+) -> Result<(), SerializationError> {
+    let raw = to_bytes(&prepare_verifying_key(key));
+    let mut file = KeyFileWriter::new(writer, VK_MAGIC, circuit, vec![raw.len() as u64])?;
+    file.section(&raw)?;
+    file.finish()
+}
+
+pub fn read_verifying_key<E: Pairing>(
+    reader: impl Read,
+) -> Result<(KeyHeader, PreparedVerifyingKey<E>), SerializationError> {
+    let mut file = KeyFileReader::new(Stream(reader), VK_MAGIC, VK_SECTIONS)?;
+    let vk = from_bytes::<_, true>(&file.section()?)?;
+    Ok((file.finish()?, vk))
+}
+
+pub fn save_verifying_key<E: Pairing>(
+    current_dir: &PathBuf,
+    name: &str,
+    key: &VerifyingKey<E>,
+    circuit: &CircuitInfo,
+) -> Result<(), SerializationError> {
+    let file_name = current_dir.join(format!("{}.vk", name));
+    let mut writer = BufWriter::new(File::create(file_name)?);
+    write_verifying_key(key, circuit, &mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Load `{name}.vk`. Verifiers usually ship without the circuit, so the key
+/// is only matched against `{name}.r1cs` when that file is present and the
+/// `prover` feature is enabled.
+pub fn load_verifying_key<E: Pairing>(
+    current_dir: &PathBuf,
+    name: &str,
+) -> Result<PreparedVerifyingKey<E>, SerializationError> {
+    let file_name = current_dir.join(format!("{}.vk", name));
+    let (_header, vk) = read_verifying_key(BufReader::new(File::open(file_name)?))?;
+    #[cfg(feature = "prover")]
+    if current_dir.join(format!("{}.r1cs", name)).exists() {
+        _header.check_circuit(&CircuitInfo::load::<E::ScalarField>(current_dir, name)?)?;
+    }
+    Ok(vk)
+}
@@ -0,0 +1,338 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::PathBuf,
+};
+
+use ark_bn254::Bn254;
+use ark_ec::{pairing::Pairing, AffineRepr};
+use ark_ff::PrimeField;
+use ark_groth16::ProvingKey;
+use keccak_hash::H256;
+use memmap2::Mmap;
+use tiny_keccak::{Hasher, Keccak};
+
+use rayon::prelude::*;
+
+use ark_serialize::{CanonicalSerialize, Compress, SerializationError, Validate};
+
+use super::{
+    from_bytes, save_verifying_key, to_bytes, CircuitInfo, KeyFileReader, KeyFileWriter, KeyHeader,
+    KeySource, Stream, DIGEST_CHUNK,
+};
+use crate::{snarkjs::read_r1cs_header, utils::invalid_data};
+
+const PK_MAGIC: &[u8; 8] = b"0gvc-pk\0";
+
+/// vk, beta_g1, delta_g1, a, b_g1, b_g2, h, l and the extension.
+const PK_SECTIONS: usize = 9;
+
+impl CircuitInfo {
+    /// Identify a circuit compiled for the scalar field `F`.
+    pub fn from_r1cs<F: PrimeField, R: Read + Seek>(
+        mut reader: R,
+    ) -> Result<Self, SerializationError> {
+        let header = read_r1cs_header::<F, _>(&mut reader)?;
+
+        reader.seek(SeekFrom::Start(0))?;
+        let mut hasher = Keccak::v256();
+        let mut buffer = vec![0u8; DIGEST_CHUNK];
+        loop {
+            let size = reader.read(&mut buffer)?;
+            if size == 0 {
+                break;
+            }
+            hasher.update(&buffer[..size]);
+        }
+        let mut r1cs_hash = H256::default();
+        hasher.finalize(&mut r1cs_hash.0);
+
+        Ok(Self {
+            r1cs_hash,
+            num_constraints: header.num_constraints as u64,
+            num_inputs: header.num_instance_variables() as u64,
+            num_variables: header.num_wires as u64,
+        })
+    }
+
+    /// Identify the circuit from `{name}.r1cs`.
+    pub fn load<F: PrimeField>(
+        current_dir: &PathBuf,
+        name: &str,
+    ) -> Result<Self, SerializationError> {
+        let file_name = current_dir.join(format!("{}.r1cs", name));
+        Self::from_r1cs::<F, _>(BufReader::new(File::open(file_name)?))
+    }
+}
+
+fn affine_list_size<A: AffineRepr>(input: &[A]) -> u64 {
+    (input.len() * A::zero().serialized_size(Compress::No)) as u64
+}
+
+fn serialize_affine_list<A: AffineRepr>(input: &[A]) -> Vec<u8> {
+    let serialize = |x: &A| {
+        let mut raw = Vec::with_capacity(128);
+        x.serialize_uncompressed(&mut raw).unwrap();
+        raw
+    };
+    input
+        .par_iter()
+        .with_min_len(16384)
+        .flat_map(serialize)
+        .collect()
+}
+
+fn deserialize_affine_list<A: AffineRepr, const CHECK: bool>(
+    raw: &[u8],
+) -> Result<Vec<A>, SerializationError> {
+    let item_length = A::zero().serialized_size(Compress::No);
+    if raw.len() % item_length != 0 {
+        return Err(invalid_data("Truncated point list"));
+    }
+
+    let check = if CHECK { Validate::Yes } else { Validate::No };
+
+    let deserialize = |x: &[u8]| A::deserialize_with_mode(x, Compress::No, check);
+    raw.par_chunks_exact(item_length)
+        .with_min_len(16384)
+        .map(deserialize)
+        .collect()
+}
+
+/// A decoded `.pk` file.
+pub struct ProvingKeyFile<E: Pairing = Bn254> {
+    pub header: KeyHeader,
+    pub key: ProvingKey<E>,
+    /// Opaque data stored along with the key, e.g. the phase-2 ceremony state.
+    pub extension: Vec<u8>,
+}
+
+pub fn write_proving_key<E: Pairing>(
+    key: &ProvingKey<E>,
+    circuit: &CircuitInfo,
+    extension: &[u8],
+    writer: impl Write,
+) -> Result<(), SerializationError> {
+    let lengths = vec![
+        key.vk.uncompressed_size() as u64,
+        key.beta_g1.uncompressed_size() as u64,
+        key.delta_g1.uncompressed_size() as u64,
+        affine_list_size(&key.a_query),
+        affine_list_size(&key.b_g1_query),
+        affine_list_size(&key.b_g2_query),
+        affine_list_size(&key.h_query),
+        affine_list_size(&key.l_query),
+        extension.len() as u64,
+    ];
+
+    let mut file = KeyFileWriter::new(writer, PK_MAGIC, circuit, lengths)?;
+    file.section(&to_bytes(&key.vk))?;
+    file.section(&to_bytes(&key.beta_g1))?;
+    file.section(&to_bytes(&key.delta_g1))?;
+    file.section(&serialize_affine_list(&key.a_query))?;
+    file.section(&serialize_affine_list(&key.b_g1_query))?;
+    file.section(&serialize_affine_list(&key.b_g2_query))?;
+    file.section(&serialize_affine_list(&key.h_query))?;
+    file.section(&serialize_affine_list(&key.l_query))?;
+    file.section(extension)?;
+    file.finish()
+}
+
+/// Read a `.pk` file. The digest is always verified; `CHECK` additionally
+/// validates that every point is on the curve and in the right subgroup.
+///
+/// Each section is buffered before decoding; [`map_proving_key`] avoids that
+/// copy for files on disk.
+pub fn read_proving_key<E: Pairing, const CHECK: bool>(
+    reader: impl Read,
+) -> Result<ProvingKeyFile<E>, SerializationError> {
+    decode_proving_key::<E, CHECK>(Stream(reader))
+}
+
+/// Memory-map a `.pk` file and decode its sections in place, so peak memory
+/// stays close to the size of the decoded key. Validation as in
+/// [`read_proving_key`].
+pub fn map_proving_key<E: Pairing, const CHECK: bool>(
+    file_name: &PathBuf,
+) -> Result<ProvingKeyFile<E>, SerializationError> {
+    let file = File::open(file_name)?;
+    // Safety: the map is dropped before returning, and key files are never
+    // modified in place, only replaced.
+    let map = unsafe { Mmap::map(&file)? };
+    #[cfg(unix)]
+    map.advise(memmap2::Advice::Sequential)?;
+    decode_proving_key::<E, CHECK>(&map[..])
+}
+
+fn decode_proving_key<'a, E: Pairing, const CHECK: bool>(
+    source: impl KeySource<'a>,
+) -> Result<ProvingKeyFile<E>, SerializationError> {
+    let mut file = KeyFileReader::new(source, PK_MAGIC, PK_SECTIONS)?;
+
+    let key = ProvingKey {
+        vk: from_bytes::<_, CHECK>(&file.section()?)?,
+        beta_g1: from_bytes::<_, CHECK>(&file.section()?)?,
+        delta_g1: from_bytes::<_, CHECK>(&file.section()?)?,
+        a_query: deserialize_affine_list::<_, CHECK>(&file.section()?)?,
+        b_g1_query: deserialize_affine_list::<_, CHECK>(&file.section()?)?,
+        b_g2_query: deserialize_affine_list::<_, CHECK>(&file.section()?)?,
+        h_query: deserialize_affine_list::<_, CHECK>(&file.section()?)?,
+        l_query: deserialize_affine_list::<_, CHECK>(&file.section()?)?,
+    };
+    let extension = file.section()?.into_owned();
+    let header = file.finish()?;
+
+    Ok(ProvingKeyFile {
+        header,
+        key,
+        extension,
+    })
+}
+
+/// Write `{name}.pk` and `{name}.vk` for the circuit in `{name}.r1cs`.
+pub fn save_key<E: Pairing>(
+    current_dir: &PathBuf,
+    name: &str,
+    key: ProvingKey<E>,
+) -> Result<(), SerializationError> {
+    let circuit = CircuitInfo::load::<E::ScalarField>(current_dir, name)?;
+
+    let file_name = current_dir.join(format!("{}.pk", name));
+    let mut writer = BufWriter::new(File::create(file_name)?);
+    write_proving_key(&key, &circuit, &[], &mut writer)?;
+    writer.flush()?;
+
+    save_verifying_key(current_dir, name, &key.vk, &circuit)
+}
+
+/// Map `{name}.pk` with [`map_proving_key`], rejecting it unless it was
+/// generated for `{name}.r1cs`.
+pub fn open_proving_key<E: Pairing, const CHECK: bool>(
+    current_dir: &PathBuf,
+    name: &str,
+) -> Result<ProvingKeyFile<E>, SerializationError> {
+    let circuit = CircuitInfo::load::<E::ScalarField>(current_dir, name)?;
+
+    let file = map_proving_key::<E, CHECK>(&current_dir.join(format!("{}.pk", name)))?;
+    file.header.check_circuit(&circuit)?;
+    Ok(file)
+}
+
+/// Load `{name}.pk`, rejecting it unless it was generated for `{name}.r1cs`.
+pub fn load_proving_key<E: Pairing, const CHECK: bool>(
+    current_dir: &PathBuf,
+    name: &str,
+) -> Result<ProvingKey<E>, SerializationError> {
+    Ok(open_proving_key::<E, CHECK>(current_dir, name)?.key)
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use crate::params::{read_verifying_key, write_verifying_key};
+    use ark_bls12_381::Bls12_381;
+    use ark_ec::{AffineRepr, CurveGroup};
+    use ark_groth16::VerifyingKey;
+    use ark_std::UniformRand;
+    use keccak_hash::keccak;
+    use rand::Rng;
+
+    pub(crate) fn random_key<E: Pairing>(rng: &mut impl Rng) -> ProvingKey<E> {
+        let mut g1 = |n: usize| -> Vec<E::G1Affine> {
+            (0..n).map(|_| E::G1::rand(rng).into_affine()).collect()
+        };
+        let vk = VerifyingKey {
+            alpha_g1: g1(1)[0],
+            beta_g2: E::G2Affine::generator(),
+            gamma_g2: E::G2Affine::generator(),
+            delta_g2: E::G2Affine::generator(),
+            gamma_abc_g1: g1(3),
+        };
+        ProvingKey {
+            vk,
+            beta_g1: g1(1)[0],
+            delta_g1: E::G1Affine::generator(),
+            a_query: g1(8),
+            b_g1_query: g1(8),
+            b_g2_query: vec![E::G2Affine::generator(); 8],
+            h_query: g1(7),
+            l_query: g1(5),
+        }
+    }
+
+    fn circuit() -> CircuitInfo {
+        CircuitInfo {
+            r1cs_hash: keccak(b"circuit"),
+            num_constraints: 4,
+            num_inputs: 3,
+            num_variables: 8,
+        }
+    }
+
+    fn check_key_file_roundtrip<E: Pairing>() {
+        let key = random_key::<E>(&mut ark_std::test_rng());
+
+        let mut raw = vec![];
+        write_proving_key(&key, &circuit(), b"extension", &mut raw).unwrap();
+        let file = read_proving_key::<E, true>(&raw[..]).unwrap();
+        assert_eq!(file.key, key);
+        let mapped = decode_proving_key::<E, true>(&raw[..]).unwrap();
+        assert_eq!(mapped.key, key);
+        assert_eq!(mapped.extension, file.extension);
+        assert_eq!(file.extension, b"extension");
+        assert_eq!(file.header.circuit, circuit());
+        assert_eq!(
+            file.header.sections.last().unwrap().0 + 9 + 32,
+            raw.len() as u64
+        );
+        file.header.check_circuit(&circuit()).unwrap();
+
+        let mut other = circuit();
+        other.r1cs_hash = keccak(b"rebuilt circuit");
+        assert!(file.header.check_circuit(&other).is_err());
+
+        let mut raw = vec![];
+        write_verifying_key(&key.vk, &circuit(), &mut raw).unwrap();
+        let (header, vk) = read_verifying_key::<E>(&raw[..]).unwrap();
+        assert_eq!(header.circuit, circuit());
+        assert_eq!(vk.vk, key.vk);
+        assert!(read_proving_key::<E, false>(&raw[..]).is_err());
+    }
+
+    #[test]
+    fn key_file_roundtrip() {
+        check_key_file_roundtrip::<Bn254>();
+        check_key_file_roundtrip::<Bls12_381>();
+    }
+
+    #[test]
+    fn key_of_another_curve_is_rejected() {
+        let key = random_key::<Bls12_381>(&mut ark_std::test_rng());
+        let mut raw = vec![];
+        write_proving_key(&key, &circuit(), &[], &mut raw).unwrap();
+        assert!(read_proving_key::<Bn254, false>(&raw[..]).is_err());
+
+        let mut raw = vec![];
+        write_verifying_key(&key.vk, &circuit(), &mut raw).unwrap();
+        assert!(read_verifying_key::<Bn254>(&raw[..]).is_err());
+    }
+
+    #[test]
+    fn corrupted_key_file_is_rejected() {
+        let key = random_key::<Bn254>(&mut ark_std::test_rng());
+        let mut raw = vec![];
+        write_proving_key(&key, &circuit(), &[], &mut raw).unwrap();
+
+        // Flip a byte in the header, in the h query, and in the digest.
+        let header = read_proving_key::<Bn254, false>(&raw[..]).unwrap().header;
+        let h_offset = header.sections[6].0 as usize + 10;
+        for position in [20, h_offset, raw.len() - 1] {
+            let mut corrupted = raw.clone();
+            corrupted[position] ^= 1;
+            assert!(read_proving_key::<Bn254, false>(&corrupted[..]).is_err());
+            assert!(decode_proving_key::<Bn254, false>(&corrupted[..]).is_err());
+        }
+        assert!(read_proving_key::<Bn254, false>(&raw[..raw.len() - 1]).is_err());
+        assert!(decode_proving_key::<Bn254, false>(&raw[..raw.len() - 1]).is_err());
+    }
+}
@@ -0,0 +1,475 @@
+//! Multi-party phase-2 trusted setup for the Groth16 parameters.
+//!
+//! The coordinator derives the initial parameters from a powers-of-tau file and
+//! the circuit with `δ = 1`. Each participant multiplies `δ` by a secret factor
+//! and publishes a [`Contribution`] proving they know it. As long as a single
+//! participant discards their factor, nobody can forge proofs.
+//!
+//! The parameters are stored as a regular `.pk` file of [`crate::params`] with
+//! the ceremony state in its extension section, so
+//! [`crate::params::load_proving_key`] loads a ceremony output as-is.
+
+use std::{
+    fs::File,
+    io::{self, BufWriter, Read, Write},
+    path::PathBuf,
+};
+
+use ark_bn254::{Bn254, Fr, G1Affine, G1Projective, G2Affine, G2Projective};
+use ark_circom::circom::R1CS;
+use ark_ec::{pairing::Pairing, AffineRepr, CurveGroup, VariableBaseMSM};
+use ark_ff::{Field, One, UniformRand, Zero};
+use ark_groth16::{ProvingKey, VerifyingKey};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rayon::prelude::*;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    params::{
+        open_proving_key, read_proving_key, save_verifying_key, write_proving_key, CircuitInfo,
+        ProvingKeyFile,
+    },
+    snarkjs::{odd_lagrange_to_h_query, PtauBases},
+};
+
+/// A participant's public record: the new `δ` and a proof of knowledge of the
+/// factor that turned the previous `δ` into it.
+#[derive(Clone, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct Contribution {
+    pub delta_after: G1Affine,
+    pub s: G1Affine,
+    pub s_delta: G1Affine,
+    pub r_delta: G2Affine,
+    /// Hash of the initial parameters and all previous contributions.
+    pub transcript: [u8; 32],
+}
+
+impl Contribution {
+    /// The digest a participant publishes to attest their contribution.
+    pub fn hash(&self) -> [u8; 32] {
+        let mut hasher = HashWriter::default();
+        self.serialize_uncompressed(&mut hasher).unwrap();
+        hasher.finalize()
+    }
+}
+
+pub struct Phase2Params {
+    pub pk: ProvingKey<Bn254>,
+    /// Hash of the initial (`δ = 1`) parameters.
+    pub cs_hash: [u8; 32],
+    pub contributions: Vec<Contribution>,
+}
+
+/// Derive the initial parameters, with `γ = δ = 1` as in snarkjs, for a circuit
+/// from the phase-1 bases returned by [`crate::snarkjs::read_ptau`].
+pub fn initialize(ptau: &PtauBases, r1cs: &R1CS<Bn254>) -> Result<ProvingKey<Bn254>, String> {
+    let num_constraints = r1cs.constraints.len();
+    let num_inputs = r1cs.num_inputs;
+    let num_variables = r1cs.num_variables;
+    let domain_size = domain_size(r1cs);
+    if ptau.tau_g1.len() != domain_size {
+        return Err(format!(
+            "Powers of tau prepared for domain {}, circuit needs {}",
+            ptau.tau_g1.len(),
+            domain_size
+        ));
+    }
+
+    // Column view of the constraint matrices: per wire, `(constraint, coefficient)`.
+    let mut a = vec![vec![]; num_variables];
+    let mut b = vec![vec![]; num_variables];
+    let mut c = vec![vec![]; num_variables];
+    for (i, (lc_a, lc_b, lc_c)) in r1cs.constraints.iter().enumerate() {
+        for (column, lc) in [(&mut a, lc_a), (&mut b, lc_b), (&mut c, lc_c)] {
+            for &(wire, coeff) in lc {
+                column[wire].push((i, coeff));
+            }
+        }
+    }
+    // arkworks binds each public input with an extra `x_i * 0 = 0` row.
+    for (i, column) in a.iter_mut().enumerate().take(num_inputs) {
+        column.push((num_constraints + i, Fr::one()));
+    }
+
+    let a_query: Vec<G1Projective> = a
+        .par_iter()
+        .map(|terms| lagrange_combination(&ptau.tau_g1, terms))
+        .collect();
+    let b_g1_query: Vec<G1Projective> = b
+        .par_iter()
+        .map(|terms| lagrange_combination(&ptau.tau_g1, terms))
+        .collect();
+    let b_g2_query: Vec<G2Projective> = b
+        .par_iter()
+        .map(|terms| lagrange_combination(&ptau.tau_g2, terms))
+        .collect();
+    let abc: Vec<G1Projective> = (0..num_variables)
+        .into_par_iter()
+        .map(|i| {
+            lagrange_combination(&ptau.beta_tau_g1, &a[i])
+                + lagrange_combination(&ptau.alpha_tau_g1, &b[i])
+                + lagrange_combination(&ptau.tau_g1, &c[i])
+        })
+        .collect();
+
+    let h_query = odd_lagrange_to_h_query(&ptau.odd_tau_g1)
+        .map_err(|e| format!("Cannot convert H query: {:?}", e))?;
+
+    let vk = VerifyingKey {
+        alpha_g1: ptau.alpha_g1,
+        beta_g2: ptau.beta_g2,
+        gamma_g2: G2Affine::generator(),
+        delta_g2: G2Affine::generator(),
+        gamma_abc_g1: G1Projective::normalize_batch(&abc[..num_inputs]),
+    };
+    Ok(ProvingKey {
+        vk,
+        beta_g1: ptau.beta_g1,
+        delta_g1: G1Affine::generator(),
+        a_query: G1Projective::normalize_batch(&a_query),
+        b_g1_query: G1Projective::normalize_batch(&b_g1_query),
+        b_g2_query: G2Projective::normalize_batch(&b_g2_query),
+        h_query,
+        l_query: G1Projective::normalize_batch(&abc[num_inputs..]),
+    })
+}
+
+/// Size of the evaluation domain arkworks uses for the circuit.
+pub fn domain_size<E: Pairing>(r1cs: &R1CS<E>) -> usize {
+    (r1cs.constraints.len() + r1cs.num_inputs).next_power_of_two()
+}
+
+fn lagrange_combination<G: AffineRepr>(bases: &[G], terms: &[(usize, G::ScalarField)]) -> G::Group {
+    terms
+        .iter()
+        .map(|&(i, coeff)| bases[i] * coeff)
+        .fold(G::Group::zero(), |acc, x| acc + x)
+}
+
+impl Phase2Params {
+    pub fn new(pk: ProvingKey<Bn254>) -> Self {
+        let cs_hash = hash_key(&pk);
+        Self {
+            pk,
+            cs_hash,
+            contributions: vec![],
+        }
+    }
+
+    /// Hash the participant of the next contribution must commit to.
+    pub fn transcript(&self) -> [u8; 32] {
+        transcript(&self.cs_hash, &self.contributions)
+    }
+
+    /// Apply a fresh random factor to `δ` and record the proof of knowledge.
+    /// Returns the contribution hash for the participant to publish.
+    pub fn contribute<R: Rng>(&mut self, rng: &mut R) -> [u8; 32] {
+        let delta = loop {
+            let delta = Fr::rand(rng);
+            if !delta.is_zero() {
+                break delta;
+            }
+        };
+        let delta_inv = delta.inverse().unwrap();
+
+        let transcript = self.transcript();
+        let s = G1Projective::rand(rng).into_affine();
+        let s_delta = (s * delta).into_affine();
+        let r = hash_to_g2(&s, &s_delta, &transcript);
+        let r_delta = (r * delta).into_affine();
+
+        self.pk.delta_g1 = (self.pk.delta_g1 * delta).into_affine();
+        self.pk.vk.delta_g2 = (self.pk.vk.delta_g2 * delta).into_affine();
+        scale_points(&mut self.pk.h_query, delta_inv);
+        scale_points(&mut self.pk.l_query, delta_inv);
+
+        let contribution = Contribution {
+            delta_after: self.pk.delta_g1,
+            s,
+            s_delta,
+            r_delta,
+            transcript,
+        };
+        let hash = contribution.hash();
+        self.contributions.push(contribution);
+        hash
+    }
+
+    /// Check the whole contribution chain against the initial parameters
+    /// recomputed with [`initialize`]. Returns the contribution hashes in order.
+    pub fn verify(&self, initial: &ProvingKey<Bn254>) -> Result<Vec<[u8; 32]>, String> {
+        if hash_key(initial) != self.cs_hash {
+            return Err("Parameters were not initialized for this circuit".into());
+        }
+
+        let (pk, init) = (&self.pk, initial);
+        let unchanged = pk.vk.alpha_g1 == init.vk.alpha_g1
+            && pk.vk.beta_g2 == init.vk.beta_g2
+            && pk.vk.gamma_g2 == init.vk.gamma_g2
+            && pk.vk.gamma_abc_g1 == init.vk.gamma_abc_g1
+            && pk.beta_g1 == init.beta_g1
+            && pk.a_query == init.a_query
+            && pk.b_g1_query == init.b_g1_query
+            && pk.b_g2_query == init.b_g2_query
+            && pk.h_query.len() == init.h_query.len()
+            && pk.l_query.len() == init.l_query.len();
+        if !unchanged {
+            return Err("Circuit-specific parameters were modified".into());
+        }
+
+        let mut current_delta = init.delta_g1;
+        let mut hashes = Vec::with_capacity(self.contributions.len());
+        for (i, contribution) in self.contributions.iter().enumerate() {
+            if contribution.transcript != transcript(&self.cs_hash, &self.contributions[..i]) {
+                return Err(format!("Contribution {} commits to a wrong transcript", i));
+            }
+            let r = hash_to_g2(
+                &contribution.s,
+                &contribution.s_delta,
+                &contribution.transcript,
+            );
+            if !same_ratio(
+                (contribution.s, contribution.s_delta),
+                (r, contribution.r_delta),
+            ) {
+                return Err(format!("Contribution {} has an invalid proof", i));
+            }
+            if !same_ratio(
+                (current_delta, contribution.delta_after),
+                (r, contribution.r_delta),
+            ) {
+                return Err(format!("Contribution {} does not match its proof", i));
+            }
+            current_delta = contribution.delta_after;
+            hashes.push(contribution.hash());
+        }
+
+        if current_delta != pk.delta_g1 {
+            return Err("Final delta does not match the last contribution".into());
+        }
+        if !same_ratio(
+            (init.delta_g1, pk.delta_g1),
+            (init.vk.delta_g2, pk.vk.delta_g2),
+        ) {
+            return Err("Inconsistent delta in G1 and G2".into());
+        }
+        if !same_ratio(
+            merge_pairs(&init.h_query, &pk.h_query),
+            (pk.vk.delta_g2, init.vk.delta_g2),
+        ) {
+            return Err("H query is not scaled by delta".into());
+        }
+        if !same_ratio(
+            merge_pairs(&init.l_query, &pk.l_query),
+            (pk.vk.delta_g2, init.vk.delta_g2),
+        ) {
+            return Err("L query is not scaled by delta".into());
+        }
+
+        Ok(hashes)
+    }
+
+    pub fn write(
+        &self,
+        circuit: &CircuitInfo,
+        writer: impl Write,
+    ) -> Result<(), SerializationError> {
+        let mut extension = vec![];
+        self.cs_hash.serialize_uncompressed(&mut extension)?;
+        self.contributions.serialize_uncompressed(&mut extension)?;
+        write_proving_key(&self.pk, circuit, &extension, writer)
+    }
+
+    pub fn read<const CHECK: bool>(reader: impl Read) -> Result<Self, SerializationError> {
+        Self::from_file(read_proving_key::<Bn254, CHECK>(reader)?)
+    }
+
+    fn from_file(file: ProvingKeyFile) -> Result<Self, SerializationError> {
+        let mut extension = &file.extension[..];
+        let cs_hash = CanonicalDeserialize::deserialize_uncompressed(&mut extension)?;
+        let contributions = CanonicalDeserialize::deserialize_uncompressed(&mut extension)?;
+        Ok(Self {
+            pk: file.key,
+            cs_hash,
+            contributions,
+        })
+    }
+
+    /// Write `{name}.pk` with the ceremony state and the matching `{name}.vk`.
+    pub fn save(&self, current_dir: &PathBuf, name: &str) -> Result<(), SerializationError> {
+        let circuit = CircuitInfo::load::<Fr>(current_dir, name)?;
+
+        let file_name = current_dir.join(format!("{}.pk", name));
+        let mut writer = BufWriter::new(File::create(file_name)?);
+        self.write(&circuit, &mut writer)?;
+        writer.flush()?;
+
+        save_verifying_key(current_dir, name, &self.pk.vk, &circuit)
+    }
+
+    /// Load `{name}.pk`, rejecting it unless it was generated for `{name}.r1cs`.
+    pub fn load(current_dir: &PathBuf, name: &str) -> Result<Self, SerializationError> {
+        Self::from_file(open_proving_key::<Bn254, true>(current_dir, name)?)
+    }
+}
+
+fn transcript(cs_hash: &[u8; 32], contributions: &[Contribution]) -> [u8; 32] {
+    let mut hasher = HashWriter::default();
+    hasher.0.update(cs_hash);
+    for contribution in contributions {
+        contribution.serialize_uncompressed(&mut hasher).unwrap();
+    }
+    hasher.finalize()
+}
+
+fn hash_key(pk: &ProvingKey<Bn254>) -> [u8; 32] {
+    let mut hasher = HashWriter::default();
+    pk.serialize_uncompressed(&mut hasher).unwrap();
+    hasher.finalize()
+}
+
+/// A G2 point nobody knows the discrete log of, bound to the proof of knowledge.
+fn hash_to_g2(s: &G1Affine, s_delta: &G1Affine, transcript: &[u8; 32]) -> G2Affine {
+    let mut hasher = HashWriter::default();
+    s.serialize_uncompressed(&mut hasher).unwrap();
+    s_delta.serialize_uncompressed(&mut hasher).unwrap();
+    hasher.0.update(transcript);
+    let mut rng = StdRng::from_seed(hasher.finalize());
+    G2Projective::rand(&mut rng).into_affine()
+}
+
+/// `e(g1.0, g2.1) == e(g1.1, g2.0)`, i.e. both pairs differ by the same factor.
+fn same_ratio(g1: (G1Affine, G1Affine), g2: (G2Affine, G2Affine)) -> bool {
+    Bn254::pairing(g1.0, g2.1) == Bn254::pairing(g1.1, g2.0)
+}
+
+/// Fold two equally scaled lists into a single pair with a random linear
+/// combination, so one pairing check covers every element.
+fn merge_pairs(before: &[G1Affine], after: &[G1Affine]) -> (G1Affine, G1Affine) {
+    let mut rng = rand::thread_rng();
+    let scalars: Vec<Fr> = (0..before.len()).map(|_| Fr::rand(&mut rng)).collect();
+    (
+        G1Projective::msm_unchecked(before, &scalars).into_affine(),
+        G1Projective::msm_unchecked(after, &scalars).into_affine(),
+    )
+}
+
+fn scale_points(points: &mut Vec<G1Affine>, scalar: Fr) {
+    let scaled: Vec<G1Projective> = points
+        .par_iter()
+        .with_min_len(1024)
+        .map(|p| *p * scalar)
+        .collect();
+    *points = G1Projective::normalize_batch(&scaled);
+}
+
+#[derive(Default)]
+struct HashWriter(Sha256);
+
+impl HashWriter {
+    fn finalize(self) -> [u8; 32] {
+        let mut output = [0u8; 32];
+        output.copy_from_slice(&self.0.finalize());
+        output
+    }
+}
+
+impl Write for HashWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::params::random_key;
+
+    #[test]
+    fn ceremony_output_proves() {
+        use crate::groth16::{test::square_r1cs, Groth16};
+        use ark_circom::CircomCircuit;
+        use ark_groth16::prepare_verifying_key;
+        use ark_poly::{EvaluationDomain, Radix2EvaluationDomain};
+
+        let mut rng = ark_std::test_rng();
+        let r1cs = square_r1cs::<Bn254>();
+
+        let n = domain_size(&r1cs);
+        let (tau, alpha, beta) = (Fr::rand(&mut rng), Fr::rand(&mut rng), Fr::rand(&mut rng));
+        let lagrange = Radix2EvaluationDomain::<Fr>::new(n)
+            .unwrap()
+            .evaluate_all_lagrange_coefficients(tau);
+        let lagrange_2n = Radix2EvaluationDomain::<Fr>::new(2 * n)
+            .unwrap()
+            .evaluate_all_lagrange_coefficients(tau);
+        let g1 = |scalars: Vec<Fr>| -> Vec<G1Affine> {
+            scalars
+                .into_iter()
+                .map(|x| (G1Affine::generator() * x).into_affine())
+                .collect()
+        };
+        let ptau = PtauBases {
+            alpha_g1: (G1Affine::generator() * alpha).into_affine(),
+            beta_g1: (G1Affine::generator() * beta).into_affine(),
+            beta_g2: (G2Affine::generator() * beta).into_affine(),
+            tau_g1: g1(lagrange.clone()),
+            tau_g2: lagrange
+                .iter()
+                .map(|x| (G2Affine::generator() * x).into_affine())
+                .collect(),
+            alpha_tau_g1: g1(lagrange.iter().map(|x| *x * alpha).collect()),
+            beta_tau_g1: g1(lagrange.iter().map(|x| *x * beta).collect()),
+            odd_tau_g1: g1(lagrange_2n.into_iter().skip(1).step_by(2).collect()),
+        };
+
+        let initial = initialize(&ptau, &r1cs).unwrap();
+        let mut params = Phase2Params::new(initial.clone());
+        params.contribute(&mut rng);
+        params.verify(&initial).unwrap();
+
+        let circuit = CircomCircuit {
+            r1cs,
+            witness: Some(vec![Fr::one(), Fr::from(9u64), Fr::from(3u64)]),
+        };
+        let proof =
+            Groth16::create_random_proof_with_reduction(circuit, &params.pk, &mut rng).unwrap();
+        let vk = prepare_verifying_key(&params.pk.vk);
+        assert!(Groth16::verify_proof(&vk, &proof, &[Fr::from(9u64)]).unwrap());
+        assert!(!Groth16::verify_proof(&vk, &proof, &[Fr::from(10u64)]).unwrap());
+    }
+
+    #[test]
+    fn contribution_chain_verifies() {
+        let mut rng = ark_std::test_rng();
+        let initial = random_key(&mut rng);
+
+        let mut params = Phase2Params::new(initial.clone());
+        let first = params.contribute(&mut rng);
+        let second = params.contribute(&mut rng);
+        assert_eq!(params.verify(&initial).unwrap(), vec![first, second]);
+
+        let mut raw = vec![];
+        params.write(&CircuitInfo::default(), &mut raw).unwrap();
+        let loaded = Phase2Params::read::<true>(&raw[..]).unwrap();
+        assert_eq!(loaded.verify(&initial).unwrap(), vec![first, second]);
+        assert_eq!(
+            read_proving_key::<Bn254, true>(&raw[..]).unwrap().key,
+            params.pk
+        );
+
+        let mut tampered = Phase2Params::read::<true>(&raw[..]).unwrap();
+        tampered.pk.l_query[0] = G1Affine::generator();
+        assert!(tampered.verify(&initial).is_err());
+
+        let mut dropped = Phase2Params::read::<true>(&raw[..]).unwrap();
+        dropped.contributions.remove(0);
+        assert!(dropped.verify(&initial).is_err());
+    }
+}
@@ -0,0 +1,352 @@
+//! Declarative verifier policies. A [`Policy`] names the circuit and pins its
+//! verifying key, lists the merkle roots the verifier trusts and the public
+//! inputs it requires; a [`PolicyVerifier`] checks a [`ProofEnvelope`] against
+//! it and explains every reason for a rejection.
+//!
+//! Times are unix seconds passed in by the caller, as in
+//! [`crate::presentation`].
+
+use std::{collections::BTreeMap, fmt, path::PathBuf};
+
+use ark_bn254::Bn254;
+use ark_ec::pairing::Pairing;
+use ark_groth16::{PreparedVerifyingKey, VerifyingKey};
+use ark_serialize::CanonicalSerialize;
+use chrono::{DateTime, NaiveDate};
+use keccak_hash::{keccak, H256};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    params::load_verifying_key,
+    types::{hex_format, ExtensionSignal, VcVerifyInput},
+    verifier::{proof_from_bytes, verify},
+};
+
+/// Keccak-256 of the compressed verifying key, which pins the circuit and the
+/// setup it came from.
+pub fn vk_fingerprint<E: Pairing>(vk: &VerifyingKey<E>) -> H256 {
+    let mut raw = Vec::with_capacity(vk.compressed_size());
+    vk.serialize_compressed(&mut raw).unwrap();
+    keccak(raw)
+}
+
+/// A root the verifier trusts, optionally only within a time window.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct AcceptedRoot {
+    pub root: H256,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid_from: Option<u64>,
+    /// The root is rejected from this time on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid_until: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Policy {
+    /// The circuit name, e.g. `check_vc`, whose `.vk` the verifier loads.
+    pub circuit: String,
+    pub vk_fingerprint: H256,
+    pub roots: Vec<AcceptedRoot>,
+    /// Extension signals the public input must carry, by index.
+    #[serde(default)]
+    pub extensions: BTreeMap<usize, ExtensionSignal>,
+    /// The proof must be bound to the verifier's challenge, see
+    /// [`crate::presentation::PresentationRequest::challenge`].
+    #[serde(default)]
+    pub require_challenge: bool,
+    /// The public current date must be within a day of the verifier's clock,
+    /// for circuits with validity checks.
+    #[serde(default)]
+    pub require_current_date: bool,
+}
+
+/// A proof as sent to a verifier.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ProofEnvelope {
+    pub circuit: String,
+    /// The compressed proof, see [`crate::verifier::proof_to_bytes`].
+    #[serde(with = "hex_format")]
+    pub proof: Vec<u8>,
+    pub public_input: VcVerifyInput,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum Rejection {
+    WrongCircuit {
+        expected: String,
+        actual: String,
+    },
+    UntrustedRoot {
+        root: H256,
+    },
+    RootNotYetValid {
+        root: H256,
+    },
+    RootExpired {
+        root: H256,
+    },
+    ExtensionMismatch {
+        index: usize,
+        expected: ExtensionSignal,
+    },
+    MissingChallenge,
+    WrongChallenge,
+    MissingCurrentDate,
+    StaleCurrentDate,
+    MalformedProof {
+        error: String,
+    },
+    InvalidProof,
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rejection::WrongCircuit { expected, actual } => {
+                write!(f, "proof is for circuit {}, expected {}", actual, expected)
+            }
+            Rejection::UntrustedRoot { root } => write!(f, "root {:?} is not trusted", root),
+            Rejection::RootNotYetValid { root } => write!(f, "root {:?} is not valid yet", root),
+            Rejection::RootExpired { root } => write!(f, "root {:?} has expired", root),
+            Rejection::ExtensionMismatch { index, expected } => {
+                write!(f, "extension {} must be {:?}", index, expected)
+            }
+            Rejection::MissingChallenge => write!(f, "proof is not bound to a challenge"),
+            Rejection::WrongChallenge => write!(f, "proof is bound to another challenge"),
+            Rejection::MissingCurrentDate => write!(f, "proof has no current date"),
+            Rejection::StaleCurrentDate => write!(f, "current date of the proof is stale"),
+            Rejection::MalformedProof { error } => write!(f, "malformed proof: {}", error),
+            Rejection::InvalidProof => write!(f, "proof does not verify"),
+        }
+    }
+}
+
+/// The outcome of a check: accepted if there is no reason to reject.
+#[derive(Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Decision {
+    pub reasons: Vec<Rejection>,
+}
+
+impl Decision {
+    pub fn accepted(&self) -> bool {
+        self.reasons.is_empty()
+    }
+}
+
+pub struct PolicyVerifier<E: Pairing = Bn254> {
+    policy: Policy,
+    vk: PreparedVerifyingKey<E>,
+}
+
+impl<E: Pairing> PolicyVerifier<E> {
+    /// Fails if the key does not match the fingerprint of the policy.
+    pub fn new(policy: Policy, vk: PreparedVerifyingKey<E>) -> Result<Self, String> {
+        if vk_fingerprint(&vk.vk) != policy.vk_fingerprint {
+            return Err(format!(
+                "Verifying key does not match the policy for {}",
+                policy.circuit
+            ));
+        }
+        Ok(Self { policy, vk })
+    }
+
+    /// Load `<circuit>.vk` from `dir`.
+    pub fn load(policy: Policy, dir: &PathBuf) -> Result<Self, String> {
+        let vk = load_verifying_key(dir, &policy.circuit)
+            .map_err(|e| format!("Cannot load verifying key: {:?}", e))?;
+        Self::new(policy, vk)
+    }
+
+    pub fn policy(&self) -> &Policy {
+        &self.policy
+    }
+
+    /// Replace the trusted roots, e.g. with
+    /// [`crate::roots::RootRegistry::accepted_roots`] after the registry
+    /// changed.
+    pub fn set_roots(&mut self, roots: Vec<AcceptedRoot>) {
+        self.policy.roots = roots;
+    }
+
+    /// Check the envelope at time `now` against the policy and, if it asks
+    /// for one, the `challenge` the verifier issued. Every violated rule is
+    /// reported; the proof itself is checked last.
+    pub fn check(&self, envelope: &ProofEnvelope, now: u64, challenge: Option<H256>) -> Decision {
+        let policy = &self.policy;
+        let input = &envelope.public_input;
+        let mut reasons = vec![];
+
+        if envelope.circuit != policy.circuit {
+            reasons.push(Rejection::WrongCircuit {
+                expected: policy.circuit.clone(),
+                actual: envelope.circuit.clone(),
+            });
+        }
+
+        for root in input.roots() {
+            let accepted = policy.roots.iter().find(|x| x.root == root);
+            match accepted {
+                None => reasons.push(Rejection::UntrustedRoot { root }),
+                Some(x) if x.valid_from.is_some_and(|t| now < t) => {
+                    reasons.push(Rejection::RootNotYetValid { root })
+                }
+                Some(x) if x.valid_until.is_some_and(|t| now >= t) => {
+                    reasons.push(Rejection::RootExpired { root })
+                }
+                Some(_) => {}
+            }
+        }
+
+        for (&index, expected) in &policy.extensions {
+            if input.extensions().get(index) != Some(expected) {
+                reasons.push(Rejection::ExtensionMismatch {
+                    index,
+                    expected: expected.clone(),
+                });
+            }
+        }
+
+        if policy.require_challenge {
+            match (input.challenge(), challenge) {
+                (None, _) => reasons.push(Rejection::MissingChallenge),
+                (Some(actual), Some(expected)) if actual == expected => {}
+                (Some(_), _) => reasons.push(Rejection::WrongChallenge),
+            }
+        }
+
+        if policy.require_current_date {
+            match input.now() {
+                None => reasons.push(Rejection::MissingCurrentDate),
+                Some(date) if !is_current(date, now) => reasons.push(Rejection::StaleCurrentDate),
+                Some(_) => {}
+            }
+        }
+
+        match proof_from_bytes::<E>(&envelope.proof) {
+            Err(error) => reasons.push(Rejection::MalformedProof { error }),
+            Ok(proof) => {
+                if !verify(&self.vk, &proof, input).unwrap_or(false) {
+                    reasons.push(Rejection::InvalidProof);
+                }
+            }
+        }
+
+        Decision { reasons }
+    }
+}
+
+/// Whether `date` is within a day of the UTC date at `now`, so provers in
+/// any time zone pass.
+fn is_current(date: NaiveDate, now: u64) -> bool {
+    let Some(today) = DateTime::from_timestamp(now as i64, 0).map(|x| x.date_naive()) else {
+        return false;
+    };
+    (date - today).num_days().abs() <= 1
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod test {
+    use super::*;
+    use crate::{
+        presentation::test::{prove, setup},
+        roots::RootRegistry,
+        sample::Sample,
+        signal::VerifyInput,
+        utils::date_to_timestamp,
+    };
+    use ark_bn254::Fr;
+    use ark_groth16::prepare_verifying_key;
+
+    #[test]
+    fn policy_decisions() {
+        let date = |x| NaiveDate::parse_from_str(x, "%Y%m%d").unwrap();
+        let now = date_to_timestamp(&date("20261019")) + 3600;
+        let challenge = H256::repeat_byte(3);
+        let public_input = Sample::public_input()
+            .with_now(date("20261019"))
+            .with_challenge(challenge);
+        let num_inputs = VerifyInput::<Fr>::to_verify_input(&public_input).len();
+        let pk = setup(num_inputs);
+
+        let policy = Policy {
+            circuit: "check_vc".to_string(),
+            vk_fingerprint: vk_fingerprint(&pk.vk),
+            roots: vec![AcceptedRoot {
+                root: public_input.roots()[0],
+                valid_from: None,
+                valid_until: Some(now + 60),
+            }],
+            extensions: [(0, ExtensionSignal::Date(Sample::threshold()))].into(),
+            require_challenge: true,
+            require_current_date: true,
+        };
+        let json = serde_json::to_string(&policy).unwrap();
+        assert_eq!(serde_json::from_str::<Policy>(&json).unwrap(), policy);
+
+        let other_pk = setup(num_inputs);
+        assert!(PolicyVerifier::new(policy.clone(), prepare_verifying_key(&other_pk.vk)).is_err());
+        let mut verifier = PolicyVerifier::new(policy, prepare_verifying_key(&pk.vk)).unwrap();
+
+        let envelope = ProofEnvelope {
+            circuit: "check_vc".to_string(),
+            proof: prove(&pk, &public_input),
+            public_input,
+        };
+        assert!(verifier.check(&envelope, now, Some(challenge)).accepted());
+
+        let decision = verifier.check(&envelope, now + 60, None);
+        assert_eq!(
+            decision.reasons,
+            vec![
+                Rejection::RootExpired {
+                    root: envelope.public_input.roots()[0]
+                },
+                Rejection::WrongChallenge,
+            ]
+        );
+        let decision = verifier.check(&envelope, now + 3 * 86400, Some(challenge));
+        assert!(decision.reasons.contains(&Rejection::StaleCurrentDate));
+
+        let mut tampered = envelope.clone();
+        tampered.circuit = "check_vc_poseidon".to_string();
+        tampered.public_input = Sample::public_input();
+        let decision = verifier.check(&tampered, now, Some(challenge));
+        assert_eq!(
+            decision.reasons,
+            vec![
+                Rejection::WrongCircuit {
+                    expected: "check_vc".to_string(),
+                    actual: "check_vc_poseidon".to_string(),
+                },
+                Rejection::MissingChallenge,
+                Rejection::MissingCurrentDate,
+                Rejection::InvalidProof,
+            ]
+        );
+        assert_eq!(
+            decision.reasons[0].to_string(),
+            "proof is for circuit check_vc_poseidon, expected check_vc"
+        );
+
+        let mut registry = RootRegistry::in_memory(1);
+        registry
+            .add(envelope.public_input.roots()[0], "flow", now)
+            .unwrap();
+        verifier.set_roots(registry.accepted_roots());
+        assert!(verifier
+            .check(&envelope, now + 60, Some(challenge))
+            .accepted());
+        registry
+            .add(H256::repeat_byte(4), "flow", now + 60)
+            .unwrap();
+        verifier.set_roots(registry.accepted_roots());
+        let decision = verifier.check(&envelope, now + 60, Some(challenge));
+        assert_eq!(
+            decision.reasons,
+            vec![Rejection::UntrustedRoot {
+                root: envelope.public_input.roots()[0]
+            }]
+        );
+    }
+}
@@ -0,0 +1,206 @@
+//! A presentation request/response protocol. The verifier issues a request
+//! with its domain, a random nonce and an expiry; the holder proves with the
+//! request's challenge as a public input and answers with the proof. A proof
+//! is then only accepted by the verifier session it was made for.
+//!
+//! Times are unix seconds passed in by the caller, since the verifier also
+//! runs in WASM without a system clock.
+
+use std::collections::HashMap;
+
+use ark_bn254::Bn254;
+use ark_ec::pairing::Pairing;
+use ark_groth16::PreparedVerifyingKey;
+use keccak_hash::{keccak, H256};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    types::{hex_format, ByteArray, VcVerifyInput},
+    verifier::{proof_from_bytes, verify},
+};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PresentationRequest {
+    /// Identifies the verifier, e.g. its origin, so a challenge issued by one
+    /// verifier never matches another.
+    pub domain: String,
+    pub nonce: ByteArray<32>,
+    /// The request is rejected from this unix time on.
+    pub expires_at: u64,
+}
+
+impl PresentationRequest {
+    /// The public `challenge` input of the circuit,
+    /// `keccak(domain || nonce || expires_at)` with the expiry little endian.
+    pub fn challenge(&self) -> H256 {
+        let mut material = self.domain.as_bytes().to_vec();
+        material.extend_from_slice(self.nonce.as_ref());
+        material.extend_from_slice(&self.expires_at.to_le_bytes());
+        keccak(material)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PresentationResponse {
+    /// The nonce of the answered request.
+    pub nonce: ByteArray<32>,
+    /// The compressed proof, see [`crate::verifier::proof_to_bytes`].
+    #[serde(with = "hex_format")]
+    pub proof: Vec<u8>,
+    pub public_input: VcVerifyInput,
+}
+
+/// Issues requests and checks the responses against them. Each request is
+/// accepted at most once, and requests are forgotten once they expire.
+pub struct PresentationVerifier<E: Pairing = Bn254> {
+    domain: String,
+    ttl: u64,
+    vk: PreparedVerifyingKey<E>,
+    pending: HashMap<[u8; 32], PresentationRequest>,
+}
+
+impl<E: Pairing> PresentationVerifier<E> {
+    /// `ttl` is the lifetime of a request in seconds.
+    pub fn new(domain: impl Into<String>, ttl: u64, vk: PreparedVerifyingKey<E>) -> Self {
+        Self {
+            domain: domain.into(),
+            ttl,
+            vk,
+            pending: HashMap::new(),
+        }
+    }
+
+    pub fn issue<R: Rng + ?Sized>(&mut self, rng: &mut R, now: u64) -> PresentationRequest {
+        self.pending.retain(|_, request| request.expires_at > now);
+
+        let request = PresentationRequest {
+            domain: self.domain.clone(),
+            nonce: ByteArray::new(rng.gen()),
+            expires_at: now.saturating_add(self.ttl),
+        };
+        self.pending
+            .insert(*AsRef::<[u8; 32]>::as_ref(&request.nonce), request.clone());
+        request
+    }
+
+    /// Whether the response proves its public input for a pending request.
+    /// The request is consumed even if the proof is invalid; errors are for
+    /// responses that do not answer a pending, unexpired request.
+    pub fn check(&mut self, response: &PresentationResponse, now: u64) -> Result<bool, String> {
+        let request = self
+            .pending
+            .remove(AsRef::<[u8; 32]>::as_ref(&response.nonce))
+            .ok_or("Unknown or already answered presentation request")?;
+        if request.expires_at <= now {
+            return Err("Presentation request has expired".to_string());
+        }
+        if response.public_input.challenge() != Some(request.challenge()) {
+            return Err("Proof is not bound to the presentation request".to_string());
+        }
+        let proof = proof_from_bytes::<E>(&response.proof)?;
+        verify(&self.vk, &proof, &response.public_input)
+    }
+}
+
+#[cfg(all(test, feature = "prover"))]
+pub(crate) mod test {
+    use super::*;
+    use crate::{groth16::Groth16, sample::Sample, signal::VerifyInput, verifier::proof_to_bytes};
+    use ark_bn254::Fr;
+    use ark_groth16::{prepare_verifying_key, ProvingKey};
+    use ark_relations::{
+        lc,
+        r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError, Variable},
+    };
+    use rand::thread_rng;
+
+    /// A circuit that only exposes the given public inputs.
+    pub(crate) struct Exposed(pub(crate) Vec<Fr>);
+
+    impl ConstraintSynthesizer<Fr> for Exposed {
+        fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+            for x in self.0 {
+                let v = cs.new_input_variable(|| Ok(x))?;
+                cs.enforce_constraint(lc!() + v, lc!() + Variable::One, lc!() + v)?;
+            }
+            Ok(())
+        }
+    }
+
+    pub(crate) fn setup(num_inputs: usize) -> ProvingKey<Bn254> {
+        let circuit = Exposed(vec![Fr::from(0u64); num_inputs]);
+        Groth16::generate_random_parameters_with_reduction(circuit, &mut thread_rng()).unwrap()
+    }
+
+    /// A proof for a circuit with exactly the public inputs of `input`.
+    pub(crate) fn prove(pk: &ProvingKey<Bn254>, input: &VcVerifyInput) -> Vec<u8> {
+        let circuit = Exposed(input.to_verify_input());
+        let proof =
+            Groth16::create_random_proof_with_reduction(circuit, pk, &mut thread_rng()).unwrap();
+        proof_to_bytes(&proof)
+    }
+
+    fn respond(
+        pk: &ProvingKey<Bn254>,
+        request: &PresentationRequest,
+        challenge: H256,
+    ) -> PresentationResponse {
+        let public_input = Sample::public_input().with_challenge(challenge);
+        PresentationResponse {
+            nonce: request.nonce.clone(),
+            proof: prove(pk, &public_input),
+            public_input,
+        }
+    }
+
+    #[test]
+    fn presentation_roundtrip() {
+        let num_inputs = VerifyInput::<Fr>::to_verify_input(&Sample::public_input()).len() + 2;
+        let pk = setup(num_inputs);
+        let mut verifier =
+            PresentationVerifier::new("verifier.example", 60, prepare_verifying_key(&pk.vk));
+        let rng = &mut thread_rng();
+
+        let request = verifier.issue(rng, 1000);
+        assert_eq!(request.expires_at, 1060);
+        let response = respond(&pk, &request, request.challenge());
+        let json = serde_json::to_string(&response).unwrap();
+        let response: PresentationResponse = serde_json::from_str(&json).unwrap();
+        assert!(verifier.check(&response, 1059).unwrap());
+        // Replayed responses are rejected.
+        assert!(verifier.check(&response, 1059).is_err());
+
+        let request = verifier.issue(rng, 1000);
+        let response = respond(&pk, &request, request.challenge());
+        assert!(verifier.check(&response, 1060).is_err());
+
+        let request = verifier.issue(rng, 1000);
+        let other = PresentationRequest {
+            domain: "other.example".to_string(),
+            ..request.clone()
+        };
+        let response = respond(&pk, &request, other.challenge());
+        assert!(verifier.check(&response, 1000).is_err());
+
+        // A proof made for another request fails to verify.
+        let request = verifier.issue(rng, 1000);
+        let mut response = respond(&pk, &request, request.challenge());
+        let other = verifier.issue(rng, 1000);
+        response.proof = respond(&pk, &other, other.challenge()).proof;
+        assert!(!verifier.check(&response, 1000).unwrap());
+    }
+
+    #[test]
+    fn expired_requests_are_pruned() {
+        let pk = setup(1);
+        let mut verifier =
+            PresentationVerifier::new("verifier.example", 60, prepare_verifying_key(&pk.vk));
+        let rng = &mut thread_rng();
+        verifier.issue(rng, 1000);
+        verifier.issue(rng, 1030);
+        assert_eq!(verifier.pending.len(), 2);
+        verifier.issue(rng, 1060);
+        assert_eq!(verifier.pending.len(), 2);
+    }
+}
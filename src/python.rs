@@ -0,0 +1,111 @@
+//! Python bindings over the JSON input types, built with maturin from
+//! `pyproject.toml`. Errors are raised as `ValueError`.
+
+use std::path::PathBuf;
+
+use ark_bn254::Bn254;
+use pyo3::{exceptions::PyValueError, prelude::*, types::PyBytes};
+
+use crate::{
+    circuit::try_circom_builder,
+    groth16,
+    params::read_verifying_key,
+    types::{VcProveInput, VcVerifyInput},
+    verifier::{proof_from_bytes, proof_to_bytes},
+};
+
+fn value_error(message: impl ToString) -> PyErr {
+    PyValueError::new_err(message.to_string())
+}
+
+fn prove_input(input_json: &str) -> PyResult<VcProveInput> {
+    serde_json::from_str(input_json).map_err(|e| value_error(format!("Invalid prove input: {}", e)))
+}
+
+/// A loaded proving key and circuit.
+#[pyclass(unsendable)]
+struct Prover(groth16::Prover);
+
+#[pymethods]
+impl Prover {
+    /// Load `{dir}/{name}.pk` and the circuit files next to it.
+    #[new]
+    fn new(dir: PathBuf, name: &str) -> PyResult<Self> {
+        let circom = try_circom_builder(&dir, name).map_err(value_error)?;
+        let prover = groth16::Prover::load::<false>(&dir, name, circom).map_err(value_error)?;
+        Ok(Self(prover))
+    }
+
+    /// Prove a `VcProveInput` JSON, returning the compressed proof.
+    fn prove<'py>(&self, py: Python<'py>, input_json: &str) -> PyResult<Bound<'py, PyBytes>> {
+        let proof = self
+            .0
+            .prove(prove_input(input_json)?)
+            .map_err(value_error)?;
+        Ok(PyBytes::new(py, &proof_to_bytes(&proof)))
+    }
+}
+
+/// Check a compressed proof against a `.vk` file content and a `VcVerifyInput` JSON.
+#[pyfunction]
+fn verify(vk: &[u8], proof: &[u8], input_json: &str) -> PyResult<bool> {
+    let (_, vk) = read_verifying_key::<Bn254>(vk)
+        .map_err(|e| value_error(format!("Cannot load verifying key: {:?}", e)))?;
+    let proof = proof_from_bytes(proof).map_err(value_error)?;
+    let input: VcVerifyInput = serde_json::from_str(input_json)
+        .map_err(|e| value_error(format!("Invalid verify input: {}", e)))?;
+    crate::verifier::verify(&vk, &proof, &input).map_err(value_error)
+}
+
+/// The storage leaves of a `VcProveInput` JSON, `VC::chunks` times
+/// `CHUNK_LEN` bytes.
+#[pyfunction]
+fn encrypt_leaf<'py>(py: Python<'py>, input_json: &str) -> PyResult<Bound<'py, PyBytes>> {
+    Ok(PyBytes::new(py, &prove_input(input_json)?.leaf()))
+}
+
+/// The merkle root implied by the leaf and proof of a `VcProveInput` JSON.
+#[pyfunction]
+fn merkle_root<'py>(py: Python<'py>, input_json: &str) -> PyResult<Bound<'py, PyBytes>> {
+    Ok(PyBytes::new(
+        py,
+        prove_input(input_json)?.merkle_root().as_bytes(),
+    ))
+}
+
+#[pymodule]
+fn vc_prove(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Prover>()?;
+    m.add_function(wrap_pyfunction!(verify, m)?)?;
+    m.add_function(wrap_pyfunction!(encrypt_leaf, m)?)?;
+    m.add_function(wrap_pyfunction!(merkle_root, m)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{sample::Sample, types::expose_secrets};
+
+    #[test]
+    fn module_functions() {
+        let vk = include_bytes!("../tests/fixtures/verifier.vk");
+        let proof = include_bytes!("../tests/fixtures/verifier.proof");
+        let input = include_str!("../tests/fixtures/verifier_input.json");
+        assert!(verify(vk, proof, input).unwrap());
+        assert!(verify(vk, &proof[1..], input).is_err());
+
+        let prove_input = Sample::input();
+        let json = expose_secrets(|| serde_json::to_string(&prove_input)).unwrap();
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let leaf = encrypt_leaf(py, &json).unwrap();
+            assert_eq!(leaf.as_bytes(), prove_input.leaf());
+            let root = merkle_root(py, &json).unwrap();
+            assert_eq!(root.as_bytes(), prove_input.merkle_root().as_bytes());
+
+            let error = merkle_root(py, "{}").unwrap_err();
+            assert!(error.is_instance_of::<PyValueError>(py));
+        });
+    }
+}
@@ -0,0 +1,199 @@
+//! Trusted merkle roots. The root of the storage flow changes with every
+//! append, so a proof made a moment ago is against an older root. A
+//! [`RootRegistry`] remembers the roots each issuer published and accepts the
+//! latest few of them, unless a root was retired explicitly.
+//!
+//! Times are unix seconds passed in by the caller, as in
+//! [`crate::presentation`].
+
+use std::path::PathBuf;
+
+use ark_ec::pairing::Pairing;
+use ark_groth16::{PreparedVerifyingKey, Proof};
+use keccak_hash::H256;
+use serde::{Deserialize, Serialize};
+
+use crate::{policy::AcceptedRoot, types::VcVerifyInput, verifier::verify};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RootEntry {
+    pub root: H256,
+    /// A label for who published the root, e.g. the storage flow or issuer.
+    pub issuer: String,
+    pub added_at: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retired_at: Option<u64>,
+}
+
+/// The roots published so far, oldest first. Of each issuer the last `window`
+/// roots are accepted; a retired root still counts towards the window, so
+/// retiring it does not bring back an older one. With a file, the registry is
+/// rewritten after every change.
+pub struct RootRegistry {
+    path: Option<PathBuf>,
+    window: usize,
+    entries: Vec<RootEntry>,
+}
+
+impl RootRegistry {
+    pub fn in_memory(window: usize) -> Self {
+        Self {
+            path: None,
+            window,
+            entries: vec![],
+        }
+    }
+
+    /// Open the JSON list at `path`, starting empty if it is missing.
+    pub fn open(path: &PathBuf, window: usize) -> Result<Self, String> {
+        let entries = match std::fs::read(path) {
+            Ok(raw) => serde_json::from_slice(&raw)
+                .map_err(|e| format!("Corrupted root registry: {}", e))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => vec![],
+            Err(e) => return Err(format!("Cannot read root registry: {}", e)),
+        };
+        Ok(Self {
+            path: Some(path.clone()),
+            window,
+            entries,
+        })
+    }
+
+    pub fn window(&self) -> usize {
+        self.window
+    }
+
+    pub fn entries(&self) -> &[RootEntry] {
+        &self.entries
+    }
+
+    pub fn get(&self, root: &H256) -> Option<&RootEntry> {
+        self.entries.iter().find(|x| &x.root == root)
+    }
+
+    /// Record a new root of `issuer`, failing if it is known already.
+    pub fn add(&mut self, root: H256, issuer: &str, now: u64) -> Result<(), String> {
+        if self.get(&root).is_some() {
+            return Err(format!("Root {:?} is already registered", root));
+        }
+        self.entries.push(RootEntry {
+            root,
+            issuer: issuer.to_string(),
+            added_at: now,
+            retired_at: None,
+        });
+        self.persist()
+    }
+
+    /// Stop accepting `root`, e.g. after a bad append.
+    pub fn retire(&mut self, root: &H256, now: u64) -> Result<(), String> {
+        let entry = self
+            .entries
+            .iter_mut()
+            .find(|x| &x.root == root)
+            .ok_or(format!("Unknown root {:?}", root))?;
+        entry.retired_at.get_or_insert(now);
+        self.persist()
+    }
+
+    /// Whether `root` is among the last `window` roots of its issuer and not
+    /// retired.
+    pub fn is_trusted(&self, root: &H256) -> bool {
+        self.accepted().any(|x| &x.root == root)
+    }
+
+    fn accepted(&self) -> impl Iterator<Item = &RootEntry> {
+        self.entries.iter().enumerate().filter_map(|(i, entry)| {
+            let newer = self.entries[i + 1..]
+                .iter()
+                .filter(|x| x.issuer == entry.issuer)
+                .count();
+            (newer < self.window && entry.retired_at.is_none()).then_some(entry)
+        })
+    }
+
+    /// The trusted roots for a [`crate::policy::Policy`], which has to be
+    /// refreshed when the registry changes.
+    pub fn accepted_roots(&self) -> Vec<AcceptedRoot> {
+        self.accepted()
+            .map(|x| AcceptedRoot {
+                root: x.root,
+                valid_from: Some(x.added_at),
+                valid_until: None,
+            })
+            .collect()
+    }
+
+    /// Fails with the first root of `input` that is not trusted.
+    pub fn check(&self, input: &VcVerifyInput) -> Result<(), String> {
+        match input.roots().into_iter().find(|x| !self.is_trusted(x)) {
+            Some(root) => Err(format!("Root {:?} is not trusted", root)),
+            None => Ok(()),
+        }
+    }
+
+    /// [`verify`] after checking that the roots of `input` are trusted.
+    pub fn verify<E: Pairing>(
+        &self,
+        vk: &PreparedVerifyingKey<E>,
+        proof: &Proof<E>,
+        input: &VcVerifyInput,
+    ) -> Result<bool, String> {
+        self.check(input)?;
+        verify(vk, proof, input)
+    }
+
+    fn persist(&self) -> Result<(), String> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        // Write aside and rename, so a crash never leaves a truncated registry.
+        let temp = path.with_extension("tmp");
+        std::fs::write(&temp, serde_json::to_vec_pretty(&self.entries).unwrap())
+            .and_then(|_| std::fs::rename(&temp, path))
+            .map_err(|e| format!("Cannot write root registry: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn root_window_and_retirement() {
+        let path = std::env::temp_dir().join(format!("root-registry-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let root = H256::repeat_byte;
+
+        let mut registry = RootRegistry::open(&path, 2).unwrap();
+        registry.add(root(1), "flow", 100).unwrap();
+        registry.add(root(2), "other", 110).unwrap();
+        registry.add(root(3), "flow", 120).unwrap();
+        assert!(registry.add(root(3), "flow", 130).is_err());
+        assert!(registry.is_trusted(&root(1)));
+
+        // Another issuer's roots do not push out older ones.
+        registry.add(root(4), "flow", 140).unwrap();
+        assert!(!registry.is_trusted(&root(1)));
+        assert!(registry.is_trusted(&root(2)));
+        assert!(registry.is_trusted(&root(3)));
+
+        registry.retire(&root(3), 150).unwrap();
+        assert!(!registry.is_trusted(&root(3)));
+        assert!(!registry.is_trusted(&root(1)));
+        assert!(registry.retire(&root(5), 150).is_err());
+        drop(registry);
+
+        let registry = RootRegistry::open(&path, 1).unwrap();
+        assert_eq!(registry.entries().len(), 4);
+        assert_eq!(registry.get(&root(3)).unwrap().retired_at, Some(150));
+        let accepted: Vec<_> = registry.accepted_roots().iter().map(|x| x.root).collect();
+        assert_eq!(accepted, vec![root(2), root(4)]);
+
+        let input = VcVerifyInput::new(chrono::NaiveDate::MIN, root(4));
+        assert!(registry.check(&input).is_ok());
+        let input = VcVerifyInput::new(chrono::NaiveDate::MIN, root(3));
+        assert!(registry.check(&input).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
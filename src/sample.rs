@@ -44,6 +44,11 @@ impl Sample {
         )
     }
 
+    /// The holder master secret of `zk-input/mock.json`, bytes 0 to 31.
+    pub fn master_secret() -> ByteArray<32> {
+        ByteArray::new(core::array::from_fn(|i| i as u8))
+    }
+
     pub fn public_input() -> VcVerifyInput {
         VcVerifyInput::new(Self::threshold(), Self::input().merkle_root())
     }
@@ -1,3 +1,4 @@
+#[cfg(feature = "prover")]
 use std::collections::HashMap;
 
 use ark_bn254::Fr;
@@ -15,7 +16,11 @@ use crate::{
 
 pub trait Signal {
     fn to_signal(&self) -> Vec<CircomBigInt>;
-    fn to_signal_fr(&self) -> Vec<Fr> {
+    /// The signals as elements of the scalar field of the proving curve.
+    fn to_signal_fr<F: PrimeField>(&self) -> Vec<F>
+    where
+        Self: Sized,
+    {
         self.to_signal().into_iter().map(bitint_to_fr).collect()
     }
 }
@@ -81,31 +86,40 @@ impl Signal for Extensions {
     }
 }
 
-fn bitint_to_fr(int: CircomBigInt) -> Fr {
+fn bitint_to_fr<F: PrimeField>(int: CircomBigInt) -> F {
     use num_bigint::BigUint;
     let uint = if int.sign() == num_bigint::Sign::Minus {
         let pub_in_abs = int.abs().to_biguint().unwrap();
-        BigUint::from(<Fr as PrimeField>::MODULUS) - pub_in_abs
+        let modulus: BigUint = F::MODULUS.into();
+        modulus - pub_in_abs
     } else {
         int.to_biguint().unwrap()
     };
-    Fr::from(uint)
+    F::from(uint)
 }
 
+#[cfg(feature = "prover")]
 pub trait ProveInput {
     fn to_prove_input(&self) -> HashMap<String, Vec<CircomBigInt>>;
+
+    /// Checks the circuit would reject anyway, run before proving to fail fast.
+    fn check(&self) -> Result<(), String> {
+        Ok(())
+    }
 }
 
-pub trait VerifyInput {
-    fn to_verify_input(&self) -> Vec<Fr>;
+pub trait VerifyInput<F: PrimeField = Fr> {
+    fn to_verify_input(&self) -> Vec<F>;
 }
 
+#[cfg(feature = "prover")]
 impl ProveInput for HashMap<String, Vec<CircomBigInt>> {
     fn to_prove_input(&self) -> HashMap<String, Vec<CircomBigInt>> {
         self.clone()
     }
 }
 
+#[cfg(feature = "prover")]
 impl ProveInput for HashMap<&'static str, Vec<CircomBigInt>> {
     fn to_prove_input(&self) -> HashMap<String, Vec<CircomBigInt>> {
         self.iter()
@@ -114,8 +128,8 @@ impl ProveInput for HashMap<&'static str, Vec<CircomBigInt>> {
     }
 }
 
-impl<T: Signal> VerifyInput for T {
-    fn to_verify_input(&self) -> Vec<Fr> {
+impl<T: Signal, F: PrimeField> VerifyInput<F> for T {
+    fn to_verify_input(&self) -> Vec<F> {
         self.to_signal_fr()
     }
 }
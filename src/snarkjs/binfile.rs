@@ -0,0 +1,172 @@
+use std::{
+    collections::HashMap,
+    io::{self, Read, Seek, SeekFrom},
+};
+
+use ark_bn254::{Fq, Fq2, Fr, G1Affine, G2Affine};
+use ark_ff::{BigInteger256, PrimeField, Zero};
+use ark_serialize::{CanonicalDeserialize, SerializationError};
+use rayon::prelude::*;
+
+pub(super) const FIELD_SIZE: usize = 32;
+pub(super) const G1_SIZE: usize = 2 * FIELD_SIZE;
+pub(super) const G2_SIZE: usize = 4 * FIELD_SIZE;
+
+pub(super) use crate::utils::invalid_data;
+
+/// The sectioned container shared by iden3 binary formats (`.zkey`, `.wtns`,
+/// `.ptau`, `.r1cs`): a 4-byte magic, a version, then `(id, size, payload)`
+/// sections in any order.
+pub(super) struct BinFile<R> {
+    reader: R,
+    sections: HashMap<u32, (u64, u64)>,
+}
+
+impl<R: Read + Seek> BinFile<R> {
+    pub fn open(mut reader: R, magic: &[u8; 4]) -> Result<Self, SerializationError> {
+        let mut actual = [0u8; 4];
+        reader.read_exact(&mut actual)?;
+        if &actual != magic {
+            return Err(invalid_data(format!(
+                "Invalid magic, expected {:?}, actual {:?}",
+                String::from_utf8_lossy(magic),
+                String::from_utf8_lossy(&actual)
+            )));
+        }
+
+        let _version = read_u32(&mut reader)?;
+        let num_sections = read_u32(&mut reader)?;
+
+        let mut sections = HashMap::new();
+        for _ in 0..num_sections {
+            let id = read_u32(&mut reader)?;
+            let size = read_u64(&mut reader)?;
+            let position = reader.stream_position()?;
+            // Only the first occurrence of a section id is meaningful in these formats.
+            sections.entry(id).or_insert((position, size));
+            reader.seek(SeekFrom::Current(size as i64))?;
+        }
+
+        Ok(Self { reader, sections })
+    }
+
+    pub fn has_section(&self, id: u32) -> bool {
+        self.sections.contains_key(&id)
+    }
+
+    /// Seek to the start of section `id` and return the reader with the section size.
+    pub fn section(&mut self, id: u32) -> Result<(&mut R, u64), SerializationError> {
+        let (position, size) = *self
+            .sections
+            .get(&id)
+            .ok_or_else(|| invalid_data(format!("Missing section {}", id)))?;
+        self.reader.seek(SeekFrom::Start(position))?;
+        Ok((&mut self.reader, size))
+    }
+
+    /// Read `num` items of `item_length` bytes each, `offset` bytes into section `id`.
+    pub fn read_points<T: Send>(
+        &mut self,
+        id: u32,
+        num: usize,
+        offset: u64,
+        item_length: usize,
+        deserialize: impl Fn(&[u8]) -> Result<T, SerializationError> + Sync + Send,
+    ) -> Result<Vec<T>, SerializationError> {
+        let (reader, size) = self.section(id)?;
+        if offset + (num * item_length) as u64 > size {
+            return Err(invalid_data(format!(
+                "Section {} too short: {} bytes, need {}",
+                id,
+                size,
+                offset + (num * item_length) as u64
+            )));
+        }
+        reader.seek(SeekFrom::Current(offset as i64))?;
+
+        let mut raw = vec![0u8; num * item_length];
+        reader.read_exact(&mut raw)?;
+
+        raw.par_chunks_exact(item_length)
+            .with_min_len(16384)
+            .map(deserialize)
+            .collect()
+    }
+}
+
+pub(super) fn read_u32(mut reader: impl Read) -> io::Result<u32> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+pub(super) fn read_u64(mut reader: impl Read) -> io::Result<u64> {
+    let mut buf = [0u8; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+/// snarkjs writes base field elements in Montgomery form, which is exactly the
+/// internal representation of arkworks.
+fn fq_montgomery(raw: &[u8]) -> Result<Fq, SerializationError> {
+    let bigint = BigInteger256::deserialize_uncompressed(raw)?;
+    Ok(Fq::new_unchecked(bigint))
+}
+
+/// Scalars in the `.zkey` coefficient section carry the Montgomery factor twice
+/// (`x * R^2`); strip one factor by reading it as a Montgomery element and
+/// re-interpreting the canonical integer once more.
+pub(super) fn fr_double_montgomery(raw: &[u8]) -> Result<Fr, SerializationError> {
+    let bigint = BigInteger256::deserialize_uncompressed(raw)?;
+    Ok(Fr::new_unchecked(Fr::new_unchecked(bigint).into_bigint()))
+}
+
+/// Scalars in `.wtns` files are plain little-endian integers.
+pub(super) fn fr_canonical(raw: &[u8]) -> Result<Fr, SerializationError> {
+    let bigint = BigInteger256::deserialize_uncompressed(raw)?;
+    Fr::from_bigint(bigint).ok_or_else(|| invalid_data("Scalar is not reduced"))
+}
+
+pub(super) fn g1_montgomery(raw: &[u8]) -> Result<G1Affine, SerializationError> {
+    let x = fq_montgomery(&raw[..FIELD_SIZE])?;
+    let y = fq_montgomery(&raw[FIELD_SIZE..G1_SIZE])?;
+    if x.is_zero() && y.is_zero() {
+        return Ok(G1Affine::identity());
+    }
+    let point = G1Affine::new_unchecked(x, y);
+    if !point.is_on_curve() {
+        return Err(invalid_data("G1 point is not on curve"));
+    }
+    Ok(point)
+}
+
+pub(super) fn g2_montgomery(raw: &[u8]) -> Result<G2Affine, SerializationError> {
+    let x = Fq2::new(
+        fq_montgomery(&raw[..FIELD_SIZE])?,
+        fq_montgomery(&raw[FIELD_SIZE..2 * FIELD_SIZE])?,
+    );
+    let y = Fq2::new(
+        fq_montgomery(&raw[2 * FIELD_SIZE..3 * FIELD_SIZE])?,
+        fq_montgomery(&raw[3 * FIELD_SIZE..G2_SIZE])?,
+    );
+    if x.is_zero() && y.is_zero() {
+        return Ok(G2Affine::identity());
+    }
+    let point = G2Affine::new_unchecked(x, y);
+    if !point.is_on_curve() {
+        return Err(invalid_data("G2 point is not on curve"));
+    }
+    Ok(point)
+}
+
+pub(super) fn read_g1(mut reader: impl Read) -> Result<G1Affine, SerializationError> {
+    let mut raw = [0u8; G1_SIZE];
+    reader.read_exact(&mut raw)?;
+    g1_montgomery(&raw)
+}
+
+pub(super) fn read_g2(mut reader: impl Read) -> Result<G2Affine, SerializationError> {
+    let mut raw = [0u8; G2_SIZE];
+    reader.read_exact(&mut raw)?;
+    g2_montgomery(&raw)
+}
@@ -0,0 +1,14 @@
+//! Interoperability with snarkjs binary artifacts.
+
+mod binfile;
+mod ptau;
+mod r1cs;
+mod wtns;
+mod zkey;
+
+pub use ptau::{read_ptau, PtauBases};
+pub use r1cs::{read_r1cs_header, R1csHeader};
+pub use wtns::{load_witness, read_witness, save_witness, write_witness};
+pub use zkey::{load_zkey, read_zkey, ZkeyMatrices, ZkeyRow};
+
+pub(crate) use zkey::odd_lagrange_to_h_query;
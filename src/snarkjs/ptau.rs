@@ -0,0 +1,131 @@
+use std::io::{Read, Seek};
+
+use ark_bn254::{Fq, G1Affine, G2Affine};
+use ark_ff::{BigInteger, PrimeField};
+use ark_serialize::SerializationError;
+
+use super::binfile::{
+    g1_montgomery, g2_montgomery, invalid_data, read_u32, BinFile, FIELD_SIZE, G1_SIZE, G2_SIZE,
+};
+
+const SECTION_HEADER: u32 = 1;
+const SECTION_ALPHA_TAU_G1: u32 = 4;
+const SECTION_BETA_TAU_G1: u32 = 5;
+const SECTION_BETA_G2: u32 = 6;
+const SECTION_LAGRANGE_TAU_G1: u32 = 12;
+const SECTION_LAGRANGE_TAU_G2: u32 = 13;
+const SECTION_LAGRANGE_ALPHA_TAU_G1: u32 = 14;
+const SECTION_LAGRANGE_BETA_TAU_G1: u32 = 15;
+
+/// The phase-1 values a Groth16 phase-2 setup is built from, expressed in the
+/// Lagrange basis of a domain of size `n`.
+#[derive(Clone, Debug)]
+pub struct PtauBases {
+    pub alpha_g1: G1Affine,
+    pub beta_g1: G1Affine,
+    pub beta_g2: G2Affine,
+    /// `L_i(τ)` in G1
+    pub tau_g1: Vec<G1Affine>,
+    /// `L_i(τ)` in G2
+    pub tau_g2: Vec<G2Affine>,
+    /// `α·L_i(τ)` in G1
+    pub alpha_tau_g1: Vec<G1Affine>,
+    /// `β·L_i(τ)` in G1
+    pub beta_tau_g1: Vec<G1Affine>,
+    /// `L_{2i+1}(τ)` in G1 over the domain of size `2n`, the snarkjs `H` basis
+    pub odd_tau_g1: Vec<G1Affine>,
+}
+
+/// Read the bases for a circuit with evaluation domain `domain_size` from a
+/// powers-of-tau file prepared with `snarkjs powersoftau prepare phase2`.
+pub fn read_ptau<R: Read + Seek>(
+    reader: R,
+    domain_size: usize,
+) -> Result<PtauBases, SerializationError> {
+    let mut file = BinFile::open(reader, b"ptau")?;
+
+    let (mut reader, _) = file.section(SECTION_HEADER)?;
+    let n8 = read_u32(&mut reader)? as usize;
+    if n8 != FIELD_SIZE {
+        return Err(invalid_data(format!("Unsupported field size {}", n8)));
+    }
+    let mut modulus = [0u8; FIELD_SIZE];
+    reader.read_exact(&mut modulus)?;
+    if modulus[..] != Fq::MODULUS.to_bytes_le()[..] {
+        return Err(invalid_data("Curve mismatch, only BN254 is supported"));
+    }
+    let power = read_u32(&mut reader)?;
+    if !domain_size.is_power_of_two() || 2 * domain_size as u64 > 1u64 << power {
+        return Err(invalid_data(format!(
+            "Powers of tau of 2^{} cannot serve a domain of size {}",
+            power, domain_size
+        )));
+    }
+    if !file.has_section(SECTION_LAGRANGE_TAU_G1) {
+        return Err(invalid_data(
+            "Missing Lagrange sections, run `snarkjs powersoftau prepare phase2` first",
+        ));
+    }
+
+    // Lagrange sections concatenate the bases for domains 1, 2, 4, ..., so the
+    // basis for a domain of size `n` starts after `n - 1` points.
+    let n = domain_size;
+    let g1_offset = ((n - 1) * G1_SIZE) as u64;
+    let g2_offset = ((n - 1) * G2_SIZE) as u64;
+
+    let tau_g1 = file.read_points(
+        SECTION_LAGRANGE_TAU_G1,
+        n,
+        g1_offset,
+        G1_SIZE,
+        g1_montgomery,
+    )?;
+    let tau_g2 = file.read_points(
+        SECTION_LAGRANGE_TAU_G2,
+        n,
+        g2_offset,
+        G2_SIZE,
+        g2_montgomery,
+    )?;
+    let alpha_tau_g1 = file.read_points(
+        SECTION_LAGRANGE_ALPHA_TAU_G1,
+        n,
+        g1_offset,
+        G1_SIZE,
+        g1_montgomery,
+    )?;
+    let beta_tau_g1 = file.read_points(
+        SECTION_LAGRANGE_BETA_TAU_G1,
+        n,
+        g1_offset,
+        G1_SIZE,
+        g1_montgomery,
+    )?;
+    let odd_tau_g1 = file
+        .read_points(
+            SECTION_LAGRANGE_TAU_G1,
+            2 * n,
+            ((2 * n - 1) * G1_SIZE) as u64,
+            G1_SIZE,
+            g1_montgomery,
+        )?
+        .into_iter()
+        .skip(1)
+        .step_by(2)
+        .collect();
+
+    let alpha_g1 = file.read_points(SECTION_ALPHA_TAU_G1, 1, 0, G1_SIZE, g1_montgomery)?[0];
+    let beta_g1 = file.read_points(SECTION_BETA_TAU_G1, 1, 0, G1_SIZE, g1_montgomery)?[0];
+    let beta_g2 = file.read_points(SECTION_BETA_G2, 1, 0, G2_SIZE, g2_montgomery)?[0];
+
+    Ok(PtauBases {
+        alpha_g1,
+        beta_g1,
+        beta_g2,
+        tau_g1,
+        tau_g2,
+        alpha_tau_g1,
+        beta_tau_g1,
+        odd_tau_g1,
+    })
+}
@@ -0,0 +1,57 @@
+use std::io::{Read, Seek};
+
+use ark_ff::{BigInteger, PrimeField};
+use ark_serialize::SerializationError;
+
+use super::binfile::{invalid_data, read_u32, read_u64, BinFile};
+
+const SECTION_HEADER: u32 = 1;
+
+/// The counts from the header section of a circom `.r1cs` file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct R1csHeader {
+    pub num_wires: u32,
+    pub num_public_outputs: u32,
+    pub num_public_inputs: u32,
+    pub num_private_inputs: u32,
+    pub num_labels: u64,
+    pub num_constraints: u32,
+}
+
+impl R1csHeader {
+    /// Instance variables as arkworks counts them, including the constant one.
+    pub fn num_instance_variables(&self) -> usize {
+        1 + self.num_public_outputs as usize + self.num_public_inputs as usize
+    }
+}
+
+/// Read only the header of a `.r1cs` file, without parsing the constraints.
+/// The file must be compiled for the field `F`.
+pub fn read_r1cs_header<F: PrimeField, R: Read + Seek>(
+    reader: R,
+) -> Result<R1csHeader, SerializationError> {
+    let mut file = BinFile::open(reader, b"r1cs")?;
+
+    let (mut reader, _) = file.section(SECTION_HEADER)?;
+    let expected = F::MODULUS.to_bytes_le();
+    let n8 = read_u32(&mut reader)? as usize;
+    if n8 != expected.len() {
+        return Err(invalid_data(format!("Unsupported field size {}", n8)));
+    }
+    let mut modulus = vec![0u8; n8];
+    reader.read_exact(&mut modulus)?;
+    if modulus != expected {
+        return Err(invalid_data(
+            "Curve mismatch, the circuit targets another field",
+        ));
+    }
+
+    Ok(R1csHeader {
+        num_wires: read_u32(&mut reader)?,
+        num_public_outputs: read_u32(&mut reader)?,
+        num_public_inputs: read_u32(&mut reader)?,
+        num_private_inputs: read_u32(&mut reader)?,
+        num_labels: read_u64(&mut reader)?,
+        num_constraints: read_u32(&mut reader)?,
+    })
+}
@@ -0,0 +1,92 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Read, Seek, Write},
+    path::PathBuf,
+};
+
+use ark_bn254::Fr;
+use ark_ff::{BigInteger, PrimeField};
+use ark_serialize::SerializationError;
+
+use super::binfile::{fr_canonical, invalid_data, read_u32, BinFile, FIELD_SIZE};
+
+const WTNS_VERSION: u32 = 2;
+const SECTION_HEADER: u32 = 1;
+const SECTION_WITNESS: u32 = 2;
+
+/// Read a witness computed by snarkjs or the circom witness generator.
+pub fn read_witness<R: Read + Seek>(reader: R) -> Result<Vec<Fr>, SerializationError> {
+    let mut file = BinFile::open(reader, b"wtns")?;
+
+    let (mut reader, _) = file.section(SECTION_HEADER)?;
+    let n8 = read_u32(&mut reader)? as usize;
+    if n8 != FIELD_SIZE {
+        return Err(invalid_data(format!("Unsupported field size {}", n8)));
+    }
+    let mut modulus = [0u8; FIELD_SIZE];
+    reader.read_exact(&mut modulus)?;
+    if modulus[..] != Fr::MODULUS.to_bytes_le()[..] {
+        return Err(invalid_data("Curve mismatch, only BN254 is supported"));
+    }
+    let num_witness = read_u32(&mut reader)? as usize;
+
+    file.read_points(SECTION_WITNESS, num_witness, 0, FIELD_SIZE, fr_canonical)
+}
+
+/// Write a witness in the `.wtns` layout so that snarkjs, rapidsnark and other
+/// provers can consume it.
+pub fn write_witness(mut writer: impl Write, witness: &[Fr]) -> Result<(), SerializationError> {
+    let modulus = Fr::MODULUS.to_bytes_le();
+
+    writer.write_all(b"wtns")?;
+    writer.write_all(&WTNS_VERSION.to_le_bytes())?;
+    writer.write_all(&2u32.to_le_bytes())?;
+
+    writer.write_all(&SECTION_HEADER.to_le_bytes())?;
+    writer.write_all(&((4 + FIELD_SIZE + 4) as u64).to_le_bytes())?;
+    writer.write_all(&(FIELD_SIZE as u32).to_le_bytes())?;
+    writer.write_all(&modulus)?;
+    writer.write_all(&(witness.len() as u32).to_le_bytes())?;
+
+    writer.write_all(&SECTION_WITNESS.to_le_bytes())?;
+    writer.write_all(&((witness.len() * FIELD_SIZE) as u64).to_le_bytes())?;
+    for x in witness {
+        writer.write_all(&x.into_bigint().to_bytes_le())?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+pub fn load_witness(current_dir: &PathBuf, name: &str) -> Result<Vec<Fr>, SerializationError> {
+    let file_name = current_dir.join(format!("{}.wtns", name));
+    read_witness(BufReader::new(File::open(file_name)?))
+}
+
+pub fn save_witness(
+    current_dir: &PathBuf,
+    name: &str,
+    witness: &[Fr],
+) -> Result<(), SerializationError> {
+    let file_name = current_dir.join(format!("{}.wtns", name));
+    write_witness(BufWriter::new(File::create(file_name)?), witness)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ark_ff::UniformRand;
+    use std::io::Cursor;
+
+    #[test]
+    fn wtns_roundtrip() {
+        let mut rng = ark_std::test_rng();
+        let witness: Vec<Fr> = (0..100).map(|_| Fr::rand(&mut rng)).collect();
+
+        let mut raw = Vec::new();
+        write_witness(&mut raw, &witness).unwrap();
+        assert_eq!(raw.len(), 12 + 12 + 40 + 12 + 100 * FIELD_SIZE);
+
+        let loaded = read_witness(Cursor::new(raw)).unwrap();
+        assert_eq!(loaded, witness);
+    }
+}
@@ -0,0 +1,260 @@
+use std::{
+    fs::File,
+    io::{BufReader, Read, Seek},
+    path::PathBuf,
+};
+
+use ark_bn254::{Bn254, Fq, Fr, G1Affine, G1Projective};
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::{BigInteger, FftField, PrimeField};
+use ark_groth16::{ProvingKey, VerifyingKey};
+use ark_poly::{EvaluationDomain, Radix2EvaluationDomain};
+use ark_serialize::SerializationError;
+
+use super::binfile::{
+    fr_double_montgomery, g1_montgomery, g2_montgomery, invalid_data, read_g1, read_g2, read_u32,
+    BinFile, FIELD_SIZE, G1_SIZE, G2_SIZE,
+};
+
+const SECTION_HEADER: u32 = 1;
+const SECTION_GROTH16_HEADER: u32 = 2;
+const SECTION_IC: u32 = 3;
+const SECTION_COEFFS: u32 = 4;
+const SECTION_A: u32 = 5;
+const SECTION_B1: u32 = 6;
+const SECTION_B2: u32 = 7;
+const SECTION_C: u32 = 8;
+const SECTION_H: u32 = 9;
+
+const GROTH16_PROTOCOL: u32 = 1;
+
+/// A linear combination as stored in the `.zkey`: `(coefficient, signal index)`.
+pub type ZkeyRow = Vec<(Fr, usize)>;
+
+/// The `A` and `B` constraint matrices embedded in a `.zkey`. snarkjs does not
+/// store `C`, and the extra rows it appends for public inputs are dropped here
+/// since arkworks adds its own.
+#[derive(Clone, Debug)]
+pub struct ZkeyMatrices {
+    pub num_constraints: usize,
+    pub num_instance_variables: usize,
+    pub num_witness_variables: usize,
+    pub a: Vec<ZkeyRow>,
+    pub b: Vec<ZkeyRow>,
+}
+
+struct Groth16Header {
+    n_vars: usize,
+    n_public: usize,
+    domain_size: usize,
+    alpha_g1: G1Affine,
+    beta_g1: G1Affine,
+    beta_g2: ark_bn254::G2Affine,
+    gamma_g2: ark_bn254::G2Affine,
+    delta_g1: G1Affine,
+    delta_g2: ark_bn254::G2Affine,
+}
+
+fn check_modulus(mut reader: impl Read, modulus: &[u8]) -> Result<(), SerializationError> {
+    let n8 = read_u32(&mut reader)? as usize;
+    if n8 != FIELD_SIZE {
+        return Err(invalid_data(format!("Unsupported field size {}", n8)));
+    }
+    let mut raw = [0u8; FIELD_SIZE];
+    reader.read_exact(&mut raw)?;
+    if raw[..] != modulus[..] {
+        return Err(invalid_data("Curve mismatch, only BN254 is supported"));
+    }
+    Ok(())
+}
+
+impl Groth16Header {
+    fn read(mut reader: impl Read) -> Result<Self, SerializationError> {
+        check_modulus(&mut reader, &Fq::MODULUS.to_bytes_le())?;
+        check_modulus(&mut reader, &Fr::MODULUS.to_bytes_le())?;
+
+        let n_vars = read_u32(&mut reader)? as usize;
+        let n_public = read_u32(&mut reader)? as usize;
+        let domain_size = read_u32(&mut reader)? as usize;
+
+        Ok(Self {
+            n_vars,
+            n_public,
+            domain_size,
+            alpha_g1: read_g1(&mut reader)?,
+            beta_g1: read_g1(&mut reader)?,
+            beta_g2: read_g2(&mut reader)?,
+            gamma_g2: read_g2(&mut reader)?,
+            delta_g1: read_g1(&mut reader)?,
+            delta_g2: read_g2(&mut reader)?,
+        })
+    }
+}
+
+/// Import a snarkjs Groth16 `.zkey` as an arkworks proving key.
+///
+/// The returned key can be used directly with [`crate::groth16::prove`]: the
+/// `H` section is converted from snarkjs' Lagrange basis into the monomial
+/// basis arkworks expects (see [`odd_lagrange_to_h_query`]).
+pub fn read_zkey<R: Read + Seek>(
+    reader: R,
+) -> Result<(ProvingKey<Bn254>, ZkeyMatrices), SerializationError> {
+    let mut file = BinFile::open(reader, b"zkey")?;
+
+    let (reader, _) = file.section(SECTION_HEADER)?;
+    let protocol = read_u32(reader)?;
+    if protocol != GROTH16_PROTOCOL {
+        return Err(invalid_data(format!("Unsupported protocol {}", protocol)));
+    }
+
+    let (reader, _) = file.section(SECTION_GROTH16_HEADER)?;
+    let header = Groth16Header::read(reader)?;
+    if header.n_public + 1 > header.n_vars || !header.domain_size.is_power_of_two() {
+        return Err(invalid_data("Inconsistent groth16 header"));
+    }
+    let n_private = header.n_vars - header.n_public - 1;
+
+    let gamma_abc_g1 =
+        file.read_points(SECTION_IC, header.n_public + 1, 0, G1_SIZE, g1_montgomery)?;
+    let a_query = file.read_points(SECTION_A, header.n_vars, 0, G1_SIZE, g1_montgomery)?;
+    let b_g1_query = file.read_points(SECTION_B1, header.n_vars, 0, G1_SIZE, g1_montgomery)?;
+    let b_g2_query = file.read_points(SECTION_B2, header.n_vars, 0, G2_SIZE, g2_montgomery)?;
+    let l_query = file.read_points(SECTION_C, n_private, 0, G1_SIZE, g1_montgomery)?;
+    let h_points = file.read_points(SECTION_H, header.domain_size, 0, G1_SIZE, g1_montgomery)?;
+    let h_query = odd_lagrange_to_h_query(&h_points)?;
+
+    let matrices = read_matrices(&mut file, &header)?;
+
+    let vk = VerifyingKey {
+        alpha_g1: header.alpha_g1,
+        beta_g2: header.beta_g2,
+        gamma_g2: header.gamma_g2,
+        delta_g2: header.delta_g2,
+        gamma_abc_g1,
+    };
+    let pk = ProvingKey {
+        vk,
+        beta_g1: header.beta_g1,
+        delta_g1: header.delta_g1,
+        a_query,
+        b_g1_query,
+        b_g2_query,
+        h_query,
+        l_query,
+    };
+    Ok((pk, matrices))
+}
+
+pub fn load_zkey(
+    current_dir: &PathBuf,
+    name: &str,
+) -> Result<(ProvingKey<Bn254>, ZkeyMatrices), SerializationError> {
+    let file_name = current_dir.join(format!("{}.zkey", name));
+    read_zkey(BufReader::new(File::open(file_name)?))
+}
+
+fn read_matrices<R: Read + Seek>(
+    file: &mut BinFile<R>,
+    header: &Groth16Header,
+) -> Result<ZkeyMatrices, SerializationError> {
+    let (mut reader, _) = file.section(SECTION_COEFFS)?;
+    let num_coeffs = read_u32(&mut reader)?;
+
+    let mut matrices = [
+        vec![ZkeyRow::new(); header.domain_size],
+        vec![ZkeyRow::new(); header.domain_size],
+    ];
+    let mut max_constraint = 0;
+    let mut raw = [0u8; FIELD_SIZE];
+    for _ in 0..num_coeffs {
+        let matrix = read_u32(&mut reader)? as usize;
+        let constraint = read_u32(&mut reader)? as usize;
+        let signal = read_u32(&mut reader)? as usize;
+        reader.read_exact(&mut raw)?;
+        let value = fr_double_montgomery(&raw)?;
+
+        if matrix > 1 || constraint >= header.domain_size || signal >= header.n_vars {
+            return Err(invalid_data("Coefficient out of range"));
+        }
+        max_constraint = max_constraint.max(constraint);
+        matrices[matrix][constraint].push((value, signal));
+    }
+
+    // The last `n_public + 1` rows are the `x_i * 0 = 0` constraints snarkjs
+    // appends to bind the public inputs.
+    let num_constraints = (max_constraint + 1).saturating_sub(header.n_public + 1);
+    let [mut a, mut b] = matrices;
+    a.truncate(num_constraints);
+    b.truncate(num_constraints);
+
+    Ok(ZkeyMatrices {
+        num_constraints,
+        num_instance_variables: header.n_public + 1,
+        num_witness_variables: header.n_vars - header.n_public - 1,
+        a,
+        b,
+    })
+}
+
+/// Convert snarkjs' `H` points into arkworks' `h_query`.
+///
+/// snarkjs stores `L_{2i+1}(τ)/δ`, the Lagrange basis of the size-`2n` domain at
+/// odd indices, and multiplies them with evaluations of `A·B - C` on the odd
+/// coset. arkworks expects `τ^j·Z(τ)/δ` for `j < n - 1` and multiplies with the
+/// coefficients of `h`. As `x^j·Z(x)` has degree below `2n`, vanishes on the even
+/// points and equals `-2·g^{(2i+1)j}` on the odd ones (`g` of order `2n`),
+/// `τ^j·Z(τ) = -2·g^j·Σ_i ω^{ij}·L_{2i+1}(τ)`: an FFT over the points followed
+/// by a coset scaling.
+pub(crate) fn odd_lagrange_to_h_query(
+    points: &[G1Affine],
+) -> Result<Vec<G1Affine>, SerializationError> {
+    let n = points.len();
+    let domain = Radix2EvaluationDomain::<Fr>::new(n)
+        .filter(|domain| domain.size() == n)
+        .ok_or_else(|| invalid_data(format!("Invalid domain size {}", n)))?;
+    let coset_gen = Fr::get_root_of_unity(2 * n as u64)
+        .ok_or_else(|| invalid_data(format!("Domain size {} too large", n)))?;
+
+    let mut points: Vec<G1Projective> = points.iter().map(|p| p.into_group()).collect();
+    domain.fft_in_place(&mut points);
+    points.truncate(n - 1);
+
+    let mut scale = -Fr::from(2u64);
+    for point in points.iter_mut() {
+        *point *= scale;
+        scale *= coset_gen;
+    }
+    Ok(G1Projective::normalize_batch(&points))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ark_ec::Group;
+    use ark_ff::{Field, One, UniformRand};
+
+    #[test]
+    fn h_query_matches_arkworks_basis() {
+        let mut rng = ark_std::test_rng();
+        let n = 16;
+        let tau = Fr::rand(&mut rng);
+        let delta_inv = Fr::rand(&mut rng);
+        let g = G1Projective::generator();
+
+        let lagrange = Radix2EvaluationDomain::<Fr>::new(2 * n)
+            .unwrap()
+            .evaluate_all_lagrange_coefficients(tau);
+        let snarkjs_h: Vec<G1Affine> = (0..n)
+            .map(|i| (g * (lagrange[2 * i + 1] * delta_inv)).into_affine())
+            .collect();
+
+        let h_query = odd_lagrange_to_h_query(&snarkjs_h).unwrap();
+
+        let zt = tau.pow([n as u64]) - Fr::one();
+        assert_eq!(h_query.len(), n - 1);
+        for (j, point) in h_query.iter().enumerate() {
+            let expected = g * (tau.pow([j as u64]) * zt * delta_inv);
+            assert_eq!(*point, expected.into_affine());
+        }
+    }
+}
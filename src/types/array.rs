@@ -1,8 +1,13 @@
+use std::fmt;
+
 use crate::Signal;
 use num_bigint::BigInt as CircomBigInt;
 use serde::{Deserialize, Serialize};
+use zeroize::Zeroize;
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+/// Fixed-size bytes, hex encoded. Keys and IVs are byte arrays, so `Debug`
+/// shows only the length.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ByteArray<const N: usize>(#[serde(with = "byte_array_format")] [u8; N]);
 
 impl<const N: usize> ByteArray<N> {
@@ -23,6 +28,18 @@ impl<const N: usize> AsRef<[u8; N]> for ByteArray<N> {
     }
 }
 
+impl<const N: usize> fmt::Debug for ByteArray<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ByteArray<{}>(<redacted>)", N)
+    }
+}
+
+impl<const N: usize> Zeroize for ByteArray<N> {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
 impl<const N: usize> Signal for ByteArray<N> {
     fn to_signal(&self) -> Vec<CircomBigInt> {
         self.0.iter().map(|x| CircomBigInt::from(*x)).collect()
@@ -12,7 +12,7 @@ pub enum ExtensionSignal {
 }
 
 pub const NUM_EXTENSIONS: usize = 16;
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
 pub struct Extensions(Vec<ExtensionSignal>);
 
 impl TryFrom<Vec<ExtensionSignal>> for Extensions {
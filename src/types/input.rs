@@ -3,19 +3,22 @@ use std::{collections::HashMap, iter::repeat};
 use super::{
     array::ByteArray,
     ext::{ExtensionSignal, Extensions},
-    vc::VC_LEN,
+    optional_date_format,
+    secret::Secret,
+    vc::{CHUNK_LEN, VC_LEN},
+    VcVerifyInput,
 };
-use ark_bn254::Fr;
 use chrono::NaiveDate;
 use keccak_hash::{keccak, H256};
 use num_bigint::BigInt as CircomBigInt;
-use serde::{Deserialize, Serialize};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
 use super::vc::VC;
 use crate::{
-    aes::encrypt,
-    signal::{ProveInput, Signal, VerifyInput},
-    utils::keccak_tuple,
+    aes::{derive_key_iv, encrypt_leaf, AesKey, AesMode, LeafCipher},
+    holder::HolderKey,
+    signal::{ProveInput, Signal},
+    utils::{keccak_tuple, poseidon_leaf, poseidon_tuple},
 };
 
 macro_rules! signal_map {
@@ -27,20 +30,85 @@ macro_rules! signal_map {
 
 pub const MERKLE_DEPTH: usize = 32;
 
+/// Position of the [`LeafCipher`] identifier in a single-chunk leaf, right
+/// after the ciphertext. See [`VcProveInput::cipher_offset`] for longer VCs.
+pub const LEAF_CIPHER_OFFSET: usize = 16 + VC_LEN + 32;
+/// Position of the GCM tag in the leaf, zero in counter mode.
+pub const LEAF_TAG_OFFSET: usize = LEAF_CIPHER_OFFSET + 1;
+
+/// The hash for the leaf commitment and the merkle tree. It must match the
+/// circuit: `check_vc` hashes with Keccak, as the storage flow does, and
+/// `check_vc_poseidon` with Poseidon, which costs far fewer constraints.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum HashMode {
+    #[default]
+    Keccak,
+    Poseidon,
+}
+
+impl HashMode {
+    pub fn hash_leaf(&self, leaf: &[u8; 256]) -> H256 {
+        match self {
+            HashMode::Keccak => keccak(leaf),
+            HashMode::Poseidon => poseidon_leaf(leaf),
+        }
+    }
+
+    pub fn hash_tuple(&self, x: H256, y: H256) -> H256 {
+        match self {
+            HashMode::Keccak => keccak_tuple(x, y),
+            HashMode::Poseidon => poseidon_tuple(x, y),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone)]
+#[serde(remote = "Self")]
 pub struct VcProveInput {
-    key: ByteArray<16>,
-    iv: ByteArray<16>,
-    data: VC,
+    key: Secret<AesKey>,
+    iv: Secret<ByteArray<16>>,
+    #[serde(default)]
+    aes_mode: AesMode,
+    pub(super) data: Secret<VC>,
     merkle_proof: Vec<H256>,
     path_index: usize,
-    extensions: Extensions,
+    pub(super) extensions: Extensions,
+    #[serde(default)]
+    pub(super) hash_mode: HashMode,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "optional_date_format"
+    )]
+    pub(super) now: Option<NaiveDate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    holder_key: Option<Secret<HolderKey>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(super) challenge: Option<H256>,
+}
+
+impl Serialize for VcProveInput {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Self::serialize(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for VcProveInput {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let input = Self::deserialize(deserializer)?;
+        input
+            .aes_mode
+            .check_iv(input.iv.as_ref())
+            .map_err(de::Error::custom)?;
+        Ok(input)
+    }
 }
 
 impl VcProveInput {
     pub fn new(
         data: VC,
-        key: ByteArray<16>,
+        key: impl Into<AesKey>,
         iv: ByteArray<16>,
         birthdate_threshold: NaiveDate,
         merkle_proof: Vec<H256>,
@@ -50,17 +118,96 @@ impl VcProveInput {
             .try_into()
             .unwrap();
         Self {
-            data,
-            key,
-            iv,
+            data: data.into(),
+            key: Secret::new(key.into()),
+            iv: iv.into(),
+            aes_mode: AesMode::Ctr,
             extensions,
             merkle_proof,
             path_index,
+            hash_mode: HashMode::Keccak,
+            now: None,
+            holder_key: None,
+            challenge: None,
+        }
+    }
+
+    /// An AES-128-CTR input whose key and IV are derived from the holder's
+    /// master secret and the serial number of the VC, see [`derive_key_iv`].
+    pub fn from_master_secret(
+        data: VC,
+        master_secret: &ByteArray<32>,
+        birthdate_threshold: NaiveDate,
+        merkle_proof: Vec<H256>,
+        path_index: usize,
+    ) -> Self {
+        let (key, iv) = derive_key_iv(master_secret, data.serial_no());
+        Self::new(data, key, iv, birthdate_threshold, merkle_proof, path_index)
+    }
+
+    /// Bind the proof to a verifier's challenge, for circuits compiled with one.
+    pub fn with_challenge(mut self, challenge: H256) -> Self {
+        self.challenge = Some(challenge);
+        self
+    }
+
+    /// The current date, required for a VC with an issuance or expiry date.
+    pub fn with_now(mut self, now: NaiveDate) -> Self {
+        self.now = Some(now);
+        self
+    }
+
+    /// Check the validity window of the VC, which the circuit enforces too.
+    pub fn check_validity(&self) -> Result<(), String> {
+        if !self.data.has_validity() {
+            return Ok(());
         }
+        let now = self
+            .now
+            .ok_or("The current date is required for a VC with validity dates")?;
+        self.data.check_validity(now)
+    }
+
+    /// The secret key of the holder named in the VC, see [`VC::with_holder`].
+    pub fn with_holder_key(mut self, holder_key: HolderKey) -> Self {
+        self.holder_key = Some(holder_key.into());
+        self
+    }
+
+    /// Check the holder key against the VC, which the circuit enforces too.
+    pub fn check_holder(&self) -> Result<(), String> {
+        let Some(holder) = self.data.holder() else {
+            return Ok(());
+        };
+        let key = self
+            .holder_key
+            .as_ref()
+            .ok_or("The holder key is required for a VC bound to a holder")?;
+        if key.public_key() != *holder {
+            return Err("The holder key does not match the VC".to_string());
+        }
+        Ok(())
+    }
+
+    /// The circuit must be compiled for the same cipher, see `circuits/vc.circom`.
+    /// Fails if the IV cannot be used in `aes_mode`.
+    pub fn with_aes_mode(mut self, aes_mode: AesMode) -> Result<Self, String> {
+        aes_mode.check_iv(self.iv.as_ref())?;
+        self.aes_mode = aes_mode;
+        Ok(self)
+    }
+
+    pub fn cipher(&self) -> LeafCipher {
+        LeafCipher::new(&self.key, self.aes_mode)
+    }
+
+    pub fn with_hash_mode(mut self, hash_mode: HashMode) -> Self {
+        self.hash_mode = hash_mode;
+        self
     }
 
     pub fn to_inputs(&self) -> HashMap<String, Vec<CircomBigInt>> {
-        signal_map! {
+        let mut inputs: HashMap<_, _> = signal_map! {
             "aesKey" => self.key,
             "aesIV" => self.iv,
             "encodedVC" => self.data,
@@ -68,43 +215,90 @@ impl VcProveInput {
             "pathElements" => self.merkle_proof(),
             "pathIndex" => self.path_index,
             "pathLength" => self.merkle_length(),
+        };
+        if let (true, Some(now)) = (self.data.has_validity(), self.now) {
+            inputs.insert("now".to_string(), now.to_signal());
+        }
+        if let (Some(_), Some(key)) = (self.data.holder(), &self.holder_key) {
+            inputs.insert("holderSecret".to_string(), key.to_signal());
+        }
+        if let Some(challenge) = self.challenge {
+            inputs.insert("challenge".to_string(), challenge.to_signal());
         }
+        inputs
     }
 
     pub fn to_verify_input(&self) -> VcVerifyInput {
         VcVerifyInput {
             root: self.merkle_root(),
+            other_roots: vec![],
             extensions: self.extensions.clone(),
+            now: self.now.filter(|_| self.data.has_validity()),
+            challenge: self.challenge,
         }
     }
 
-    pub fn plaintext(&self) -> [u8; VC_LEN + 32] {
+    pub fn plaintext(&self) -> Vec<u8> {
         self.data.plaintext()
     }
 
-    pub fn ciphertext(&self) -> [u8; VC_LEN + 32] {
-        let plaintext = self.data.plaintext();
-        encrypt(self.key.as_ref(), self.iv.as_ref(), &plaintext)
+    /// The ciphertext and the GCM tag, zero in counter mode.
+    pub fn encrypted(&self) -> (Vec<u8>, [u8; 16]) {
+        let plaintext = self.plaintext();
+        // The IV was checked against the mode when the input was built.
+        encrypt_leaf(&self.key, self.aes_mode, self.iv.as_ref(), &plaintext).unwrap()
     }
 
-    pub fn leaf(&self) -> [u8; 256] {
-        let mut leaf = [0u8; 256];
+    pub fn ciphertext(&self) -> Vec<u8> {
+        self.encrypted().0
+    }
+
+    /// The number of consecutive leaves the VC is stored in, see [`VC::chunks`].
+    pub fn chunks(&self) -> usize {
+        self.data.chunks()
+    }
+
+    pub fn cipher_offset(&self) -> usize {
+        16 + self.plaintext().len()
+    }
+
+    /// The stored bytes, `CHUNK_LEN` per leaf: the IV, the ciphertext, the
+    /// cipher id, the GCM tag and zero padding.
+    pub fn leaf(&self) -> Vec<u8> {
+        let (ciphertext, tag) = self.encrypted();
+        let cipher_offset = 16 + ciphertext.len();
+        let mut leaf = vec![0u8; self.chunks() * CHUNK_LEN];
         leaf[0..16].copy_from_slice(self.iv.as_ref());
-        leaf[16..16 + VC_LEN + 32].copy_from_slice(&self.ciphertext());
+        leaf[16..cipher_offset].copy_from_slice(&ciphertext);
+        leaf[cipher_offset] = self.cipher().id();
+        leaf[cipher_offset + 1..cipher_offset + 17].copy_from_slice(&tag);
         leaf
     }
 
+    /// The hash of the leaf or, for a VC spanning several leaves, the root of
+    /// their subtree. `merkle_proof` and `path_index` start from there.
     pub fn leaf_hash(&self) -> H256 {
-        keccak(self.leaf())
+        let mut hashes: Vec<H256> = self
+            .leaf()
+            .chunks_exact(CHUNK_LEN)
+            .map(|chunk| self.hash_mode.hash_leaf(chunk.try_into().unwrap()))
+            .collect();
+        while hashes.len() > 1 {
+            hashes = hashes
+                .chunks_exact(2)
+                .map(|pair| self.hash_mode.hash_tuple(pair[0], pair[1]))
+                .collect();
+        }
+        hashes[0]
     }
 
     pub fn merkle_root(&self) -> H256 {
         let mut hash = self.leaf_hash();
         for (i, &proof) in self.merkle_proof.iter().enumerate() {
             hash = if self.path_index & (0x1 << i) != 0 {
-                keccak_tuple(proof, hash)
+                self.hash_mode.hash_tuple(proof, hash)
             } else {
-                keccak_tuple(hash, proof)
+                self.hash_mode.hash_tuple(hash, proof)
             };
         }
         hash
@@ -129,32 +323,171 @@ impl ProveInput for VcProveInput {
     fn to_prove_input(&self) -> HashMap<String, Vec<CircomBigInt>> {
         self.to_inputs()
     }
-}
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct VcVerifyInput {
-    root: H256,
-    extensions: Extensions,
+    fn check(&self) -> Result<(), String> {
+        self.check_validity()?;
+        self.check_holder()
+    }
 }
 
-impl VcVerifyInput {
-    pub fn new(birthdate_threshold: NaiveDate, root: H256) -> Self {
-        let extensions = vec![ExtensionSignal::Date(birthdate_threshold)]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        aes::decrypt_leaf,
+        sample::Sample,
+        types::{expose_secrets, NUM_EXTENSIONS},
+    };
+
+    #[test]
+    fn leaf_records_cipher() {
+        let leaf = Sample::input().leaf();
+        assert_eq!(leaf[LEAF_CIPHER_OFFSET], LeafCipher::Aes128Ctr.id());
+        assert!(leaf[LEAF_CIPHER_OFFSET..].iter().all(|x| *x == 0));
+
+        let key = AesKey::from(ByteArray::new([9u8; 32]));
+        let input = VcProveInput::new(
+            Sample::vc(),
+            key.clone(),
+            ByteArray::new(*b"uniqueiv1234\0\0\0\0"),
+            Sample::threshold(),
+            Sample::merkle_path(3),
+            0,
+        );
+        assert_eq!(input.leaf()[LEAF_CIPHER_OFFSET], LeafCipher::Aes256Ctr.id());
+
+        let input = input.with_aes_mode(AesMode::Gcm).unwrap();
+        let leaf = input.leaf();
+        let cipher = LeafCipher::from_id(leaf[LEAF_CIPHER_OFFSET]).unwrap();
+        assert_eq!(cipher, LeafCipher::Aes256Gcm);
+
+        let iv = leaf[..16].try_into().unwrap();
+        let ciphertext = &leaf[16..LEAF_CIPHER_OFFSET];
+        let tag = leaf[LEAF_TAG_OFFSET..LEAF_TAG_OFFSET + 16]
             .try_into()
             .unwrap();
-        Self { extensions, root }
+        let plaintext = decrypt_leaf(&key, cipher.mode(), iv, ciphertext, tag).unwrap();
+        assert_eq!(plaintext, input.plaintext());
     }
 
-    pub fn to_public_inputs(&self) -> Vec<Fr> {
-        [&self.root as &dyn Signal, &self.extensions]
-            .into_iter()
-            .flat_map(Signal::to_signal_fr)
-            .collect()
+    #[test]
+    fn gcm_rejects_iv_tail() {
+        let input = VcProveInput::new(
+            Sample::vc(),
+            ByteArray::new(*b"verysecretkey123"),
+            ByteArray::new(*b"uniqueiv12345678"),
+            Sample::threshold(),
+            vec![],
+            0,
+        );
+        let mut json = expose_secrets(|| serde_json::to_value(&input)).unwrap();
+        let err = input.with_aes_mode(AesMode::Gcm).err().unwrap();
+        assert_eq!(err, "The last 4 bytes of a GCM IV must be zero");
+
+        json["aes_mode"] = serde_json::json!("gcm");
+        let err = serde_json::from_value::<VcProveInput>(json).err().unwrap();
+        assert!(err.to_string().contains("GCM IV"));
     }
-}
 
-impl VerifyInput for VcVerifyInput {
-    fn to_verify_input(&self) -> Vec<Fr> {
-        self.to_public_inputs()
+    #[test]
+    fn mock_input_uses_derived_key() {
+        let mock: VcProveInput =
+            serde_json::from_str(include_str!("../../zk-input/mock.json")).unwrap();
+        let derived = VcProveInput::from_master_secret(
+            (*mock.data).clone(),
+            &Sample::master_secret(),
+            Sample::threshold(),
+            mock.merkle_proof.clone(),
+            mock.path_index,
+        );
+        assert_eq!(derived.leaf(), mock.leaf());
+        assert_eq!(derived.merkle_root(), mock.merkle_root());
+    }
+
+    #[test]
+    fn multi_chunk_leaf() {
+        let key = ByteArray::new(*b"verysecretkey123");
+        let iv = ByteArray::new(*b"uniqueiv12345678");
+        let vc = Sample::vc()
+            .with_extra(b"42 Long Street, Some City".repeat(8))
+            .unwrap();
+        let input = VcProveInput::new(vc, key, iv, Sample::threshold(), vec![], 0);
+        assert_eq!(input.chunks(), 2);
+
+        let leaf = input.leaf();
+        assert_eq!(leaf.len(), 2 * CHUNK_LEN);
+        assert_eq!(input.cipher_offset(), 2 * CHUNK_LEN - 17);
+
+        let (left, right) = leaf.split_at(CHUNK_LEN);
+        let expected = keccak_tuple(keccak(left), keccak(right));
+        assert_eq!(input.leaf_hash(), expected);
+        assert_eq!(input.merkle_root(), expected);
+
+        let plaintext = decrypt_leaf(
+            &input.key,
+            AesMode::Ctr,
+            leaf[..16].try_into().unwrap(),
+            &leaf[16..input.cipher_offset()],
+            &[0u8; 16],
+        )
+        .unwrap();
+        assert_eq!(plaintext, input.plaintext());
+    }
+
+    #[test]
+    fn validity_is_checked_before_proving() {
+        let date = |x| NaiveDate::parse_from_str(x, "%Y%m%d").unwrap();
+        let vc = Sample::vc().with_validity(Some(date("20240101")), Some(date("20250101")));
+        let input = VcProveInput::new(
+            vc,
+            ByteArray::new(*b"verysecretkey123"),
+            ByteArray::new(*b"uniqueiv12345678"),
+            Sample::threshold(),
+            Sample::merkle_path(3),
+            0,
+        );
+        assert!(input.check().is_err());
+        assert!(!input.to_inputs().contains_key("now"));
+
+        let expired = input.clone().with_now(date("20250101"));
+        assert!(expired.check().is_err());
+
+        let input = input.with_now(date("20240615"));
+        assert!(input.check().is_ok());
+        assert_eq!(input.to_inputs()["now"], date("20240615").to_signal());
+        let public_inputs = input.to_verify_input().to_public_inputs::<ark_bn254::Fr>();
+        assert_eq!(public_inputs.len(), 2 + NUM_EXTENSIONS + 1);
+
+        let input = Sample::input().with_now(date("20240615"));
+        assert!(!input.to_inputs().contains_key("now"));
+        assert_eq!(input.to_verify_input().now, None);
+    }
+
+    #[test]
+    fn holder_key_is_checked_before_proving() {
+        let key = HolderKey::from_seed(b"alice");
+        let vc = Sample::vc().with_holder(key.public_key());
+        let input = VcProveInput::new(
+            vc,
+            ByteArray::new(*b"verysecretkey123"),
+            ByteArray::new(*b"uniqueiv12345678"),
+            Sample::threshold(),
+            Sample::merkle_path(3),
+            0,
+        );
+        assert!(input.check().is_err());
+
+        let stolen = input
+            .clone()
+            .with_holder_key(HolderKey::from_seed(b"mallory"));
+        assert!(stolen.check().is_err());
+
+        let input = input.with_holder_key(key.clone());
+        assert!(input.check().is_ok());
+        assert_eq!(input.to_inputs()["holderSecret"], key.to_signal());
+        assert!(!Sample::input()
+            .with_holder_key(key)
+            .to_inputs()
+            .contains_key("holderSecret"));
     }
 }
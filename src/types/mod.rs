@@ -1,29 +1,42 @@
 mod array;
 mod ext;
+#[cfg(feature = "prover")]
 mod input;
+#[cfg(feature = "prover")]
+mod multi_input;
+mod secret;
 mod vc;
+mod verify_input;
+mod w3c;
 
 pub use array::ByteArray;
 pub use ext::{ExtensionSignal, Extensions, NUM_EXTENSIONS};
-pub use input::{VcProveInput, VcVerifyInput, MERKLE_DEPTH};
-pub use vc::VC;
+#[cfg(feature = "prover")]
+pub use input::{HashMode, VcProveInput, LEAF_CIPHER_OFFSET, LEAF_TAG_OFFSET, MERKLE_DEPTH};
+#[cfg(feature = "prover")]
+pub use multi_input::MultiVcProveInput;
+pub(crate) use secret::check_exposed;
+pub use secret::{expose_secrets, Secret};
+pub use vc::{extra_len, CHUNK_LEN, EXTRA_MAX_LEN, HOLDER_LEN, VALIDITY_LEN, VC};
+pub use verify_input::VcVerifyInput;
+pub use w3c::{ExtensionKind, ExtensionMapping, MappedVc, SerialEncoding, W3cMapping};
 
-mod serial_no_format {
+pub(crate) mod hex_format {
     use serde::{self, de::Error, Deserialize, Deserializer, Serializer};
 
-    pub fn serialize<S>(serial_no: &Vec<u8>, serializer: S) -> Result<S::Ok, S::Error>
+    pub fn serialize<S>(bytes: &Vec<u8>, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        serializer.serialize_str(&hex::encode(serial_no))
+        serializer.serialize_str(&hex::encode(bytes))
     }
 
     pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<u8>, D::Error>
     where
         D: Deserializer<'de>,
     {
-        let hex_serial_no = String::deserialize(deserializer)?;
-        hex::decode(hex_serial_no).map_err(Error::custom)
+        let hex_bytes = String::deserialize(deserializer)?;
+        hex::decode(hex_bytes).map_err(Error::custom)
     }
 }
 
@@ -47,3 +60,25 @@ mod birthdate_format {
         NaiveDate::parse_from_str(&s, "%Y%m%d").map_err(Error::custom)
     }
 }
+
+mod optional_date_format {
+    use chrono::NaiveDate;
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S>(date: &Option<NaiveDate>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match date {
+            Some(date) => super::birthdate_format::serialize(date, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<NaiveDate>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        super::birthdate_format::deserialize(deserializer).map(Some)
+    }
+}
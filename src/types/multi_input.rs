@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+
+use num_bigint::BigInt as CircomBigInt;
+use serde::{Deserialize, Serialize};
+
+use super::{VcProveInput, VcVerifyInput};
+use crate::signal::ProveInput;
+
+/// Several VCs proven in one statement by `VerifyMultiVC`, each with its own
+/// merkle proof and root, so that `CustomMultiCheck` can relate their fields.
+///
+/// The circuit is compiled for one hash mode, cipher, chunk count and set of
+/// validity and holder sections, and takes one set of extensions, current date
+/// and challenge, so all VCs must agree on them.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(try_from = "Vec<VcProveInput>", into = "Vec<VcProveInput>")]
+pub struct MultiVcProveInput(Vec<VcProveInput>);
+
+impl TryFrom<Vec<VcProveInput>> for MultiVcProveInput {
+    type Error = &'static str;
+
+    fn try_from(value: Vec<VcProveInput>) -> Result<Self, Self::Error> {
+        let first = value.first().ok_or("No VC to prove")?;
+        for input in &value[1..] {
+            if input.extensions != first.extensions {
+                return Err("VCs have different extensions");
+            }
+            if input.hash_mode != first.hash_mode {
+                return Err("VCs have different hash modes");
+            }
+            if input.cipher() != first.cipher() {
+                return Err("VCs have different leaf ciphers");
+            }
+            if input.chunks() != first.chunks() {
+                return Err("VCs span different numbers of leaves");
+            }
+            if input.data.has_validity() != first.data.has_validity() || input.now != first.now {
+                return Err("VCs have different validity checks");
+            }
+            if input.data.holder().is_some() != first.data.holder().is_some() {
+                return Err("VCs are not all bound to holders");
+            }
+            if input.challenge != first.challenge {
+                return Err("VCs have different challenges");
+            }
+        }
+        Ok(MultiVcProveInput(value))
+    }
+}
+
+impl From<MultiVcProveInput> for Vec<VcProveInput> {
+    fn from(value: MultiVcProveInput) -> Self {
+        value.0
+    }
+}
+
+impl MultiVcProveInput {
+    pub fn inputs(&self) -> &[VcProveInput] {
+        &self.0
+    }
+
+    /// Per-VC signals are concatenated in order, matching the leading `n`
+    /// dimension of the circuit inputs; the extensions, date and challenge are
+    /// shared.
+    pub fn to_inputs(&self) -> HashMap<String, Vec<CircomBigInt>> {
+        let mut inputs: HashMap<String, Vec<CircomBigInt>> = HashMap::new();
+        for (i, input) in self.0.iter().enumerate() {
+            for (name, signal) in input.to_inputs() {
+                if !["extensions", "now", "challenge"].contains(&name.as_str()) || i == 0 {
+                    inputs.entry(name).or_default().extend(signal);
+                }
+            }
+        }
+        inputs
+    }
+
+    pub fn to_verify_input(&self) -> VcVerifyInput {
+        let mut verify_input = self.0[0].to_verify_input();
+        verify_input.other_roots = self.0[1..].iter().map(|x| x.merkle_root()).collect();
+        verify_input
+    }
+}
+
+impl ProveInput for MultiVcProveInput {
+    fn to_prove_input(&self) -> HashMap<String, Vec<CircomBigInt>> {
+        self.to_inputs()
+    }
+
+    fn check(&self) -> Result<(), String> {
+        self.0.iter().try_for_each(ProveInput::check)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        aes::AesMode,
+        sample::Sample,
+        types::{expose_secrets, ByteArray, HashMode},
+    };
+    use ark_bn254::Fr;
+
+    fn diploma() -> VcProveInput {
+        let key = ByteArray::new(*b"anothersecretkey");
+        let iv = ByteArray::new(*b"anotheriv1234567");
+        VcProveInput::new(
+            Sample::vc(),
+            key,
+            iv,
+            Sample::threshold(),
+            Sample::merkle_path(5),
+            3,
+        )
+    }
+
+    #[test]
+    fn multi_input_signals() {
+        let (id, diploma) = (Sample::input(), diploma());
+        let multi = MultiVcProveInput::try_from(vec![id.clone(), diploma.clone()]).unwrap();
+
+        let single = id.to_inputs();
+        let inputs = multi.to_inputs();
+        for (name, signal) in &single {
+            let expected = if name == "extensions" { 1 } else { 2 };
+            assert_eq!(inputs[name].len(), expected * signal.len(), "{}", name);
+        }
+        assert_eq!(inputs["pathIndex"], vec![0.into(), 3.into()]);
+
+        let verify_input = multi.to_verify_input();
+        assert_eq!(
+            verify_input.roots(),
+            vec![id.merkle_root(), diploma.merkle_root()]
+        );
+        let public_inputs = verify_input.to_public_inputs::<Fr>();
+        assert_eq!(
+            public_inputs[2..],
+            diploma.to_verify_input().to_public_inputs::<Fr>()[..]
+        );
+
+        let json = expose_secrets(|| serde_json::to_value(&multi)).unwrap();
+        assert_eq!(json.as_array().unwrap().len(), 2);
+        let multi: MultiVcProveInput = serde_json::from_value(json).unwrap();
+        assert_eq!(multi.inputs().len(), 2);
+    }
+
+    #[test]
+    fn new_multi_verify_input() {
+        let (id, diploma) = (Sample::input(), diploma());
+        let multi = MultiVcProveInput::try_from(vec![id.clone(), diploma.clone()]).unwrap();
+        let verify_input = VcVerifyInput::new_multi(
+            Sample::threshold(),
+            id.merkle_root(),
+            vec![diploma.merkle_root()],
+        );
+        assert_eq!(
+            verify_input.to_public_inputs::<Fr>(),
+            multi.to_verify_input().to_public_inputs::<Fr>()
+        );
+    }
+
+    #[test]
+    fn multi_input_must_agree() {
+        assert!(MultiVcProveInput::try_from(vec![]).is_err());
+        let poseidon = diploma().with_hash_mode(HashMode::Poseidon);
+        assert!(MultiVcProveInput::try_from(vec![Sample::input(), poseidon]).is_err());
+        let gcm = VcProveInput::new(
+            Sample::vc(),
+            ByteArray::new(*b"anothersecretkey"),
+            ByteArray::new(*b"anotheriv123\0\0\0\0"),
+            Sample::threshold(),
+            Sample::merkle_path(3),
+            0,
+        )
+        .with_aes_mode(AesMode::Gcm)
+        .unwrap();
+        assert!(MultiVcProveInput::try_from(vec![Sample::input(), gcm]).is_err());
+    }
+}
@@ -0,0 +1,110 @@
+//! Wrappers for key material and credential data, which must not end up in
+//! logs, core dumps or JSON by accident.
+
+use std::{cell::Cell, fmt, ops::Deref};
+
+use serde::{ser::Error, Deserialize, Deserializer, Serialize, Serializer};
+use zeroize::Zeroize;
+
+thread_local! {
+    static EXPOSED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Run `f` with serialization of [`Secret`]s allowed on this thread, e.g. to
+/// write a holder bundle or a prove input for another process.
+pub fn expose_secrets<R>(f: impl FnOnce() -> R) -> R {
+    struct Reset(bool);
+    impl Drop for Reset {
+        fn drop(&mut self) {
+            EXPOSED.with(|x| x.set(self.0));
+        }
+    }
+
+    let _reset = Reset(EXPOSED.with(|x| x.replace(true)));
+    f()
+}
+
+/// A value that is redacted in `Debug`, wiped on drop and serialized only
+/// inside [`expose_secrets`]; serializing it elsewhere is an error. It
+/// deserializes as the bare value, so existing JSON inputs load unchanged.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Secret<T: Zeroize>(T);
+
+impl<T: Zeroize> Secret<T> {
+    pub fn new(value: T) -> Self {
+        Secret(value)
+    }
+}
+
+impl<T: Zeroize> From<T> for Secret<T> {
+    fn from(value: T) -> Self {
+        Secret(value)
+    }
+}
+
+impl<T: Zeroize> Deref for Secret<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Zeroize> Drop for Secret<T> {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl<T: Zeroize> fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("<redacted>")
+    }
+}
+
+/// Fails outside [`expose_secrets`], for `Serialize` impls of key material
+/// that is not wrapped in a [`Secret`].
+pub(crate) fn check_exposed<E: Error>() -> Result<(), E> {
+    if !EXPOSED.with(Cell::get) {
+        return Err(E::custom(
+            "Refusing to serialize a secret outside `expose_secrets`",
+        ));
+    }
+    Ok(())
+}
+
+impl<T: Zeroize + Serialize> Serialize for Secret<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        check_exposed()?;
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de, T: Zeroize + Deserialize<'de>> Deserialize<'de> for Secret<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(Secret)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::types::ByteArray;
+
+    #[test]
+    fn secret_is_redacted_and_opt_in() {
+        let secret: Secret<ByteArray<4>> = serde_json::from_str(r#""deadbeef""#).unwrap();
+        assert_eq!(*secret, ByteArray::new([0xde, 0xad, 0xbe, 0xef]));
+        assert_eq!(format!("{:?}", secret), "<redacted>");
+        assert_eq!(format!("{:?}", *secret), "ByteArray<4>(<redacted>)");
+
+        assert!(serde_json::to_string(&secret).is_err());
+        let json = expose_secrets(|| serde_json::to_string(&secret).unwrap());
+        assert_eq!(json, r#""deadbeef""#);
+        assert!(serde_json::to_string(&secret).is_err());
+
+        let mut bytes = ByteArray::new([1u8; 4]);
+        bytes.zeroize();
+        assert_eq!(bytes, ByteArray::new([0u8; 4]));
+    }
+}
@@ -1,25 +1,84 @@
-use crate::utils::{date_to_timestamp, encode_fixed_length};
+use std::fmt;
+
+use crate::{
+    holder::HolderPublicKey,
+    utils::{date_to_timestamp, encode_fixed_length},
+};
 use chrono::NaiveDate;
 use keccak_hash::{keccak, H256};
 
-use serde::{Deserialize, Serialize};
+use serde::{de::Error, Deserialize, Deserializer, Serialize};
+use zeroize::Zeroize;
 
-use super::{birthdate_format, serial_no_format};
+use super::{birthdate_format, hex_format, optional_date_format};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// The claims of a credential. `Debug` shows only the shape of the encoding,
+/// and the claims are wiped on drop; wrap it in a [`super::Secret`] to keep it
+/// out of JSON too.
+#[derive(Clone, Serialize, Deserialize)]
 pub struct VC {
     name: String,
     age: u8,
     #[serde(with = "birthdate_format")]
     birth_date: NaiveDate,
     edu_level: u8,
-    #[serde(with = "serial_no_format")]
+    #[serde(with = "hex_format")]
     serial_no: Vec<u8>,
+    /// Attributes beyond the fixed fields, e.g. a full address or more dates,
+    /// encoded by the issuer and decoded by `CustomCheckExtra` in the circuit.
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        serialize_with = "hex_format::serialize",
+        deserialize_with = "deserialize_extra"
+    )]
+    extra: Vec<u8>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "optional_date_format"
+    )]
+    issued_at: Option<NaiveDate>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "optional_date_format"
+    )]
+    expires_at: Option<NaiveDate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    holder: Option<HolderPublicKey>,
 }
 
-const NAME_MAX_LEN: usize = 16;
-const SERIAL_MAX_LEN: usize = 32;
+pub(super) const NAME_MAX_LEN: usize = 16;
+pub(super) const SERIAL_MAX_LEN: usize = 32;
 pub const VC_LEN: usize = 79;
+/// The extra attributes are prefixed with their length as a `u16`.
+pub const EXTRA_MAX_LEN: usize = u16::MAX as usize;
+
+/// The size of a storage flow entry, which is a merkle leaf.
+pub const CHUNK_LEN: usize = 256;
+/// Bytes of a leaf besides the encoded VC: the IV, the VC hash, the cipher id
+/// and the GCM tag.
+const LEAF_OVERHEAD: usize = 16 + 32 + 1 + 16;
+
+/// The validity section, after the fixed fields of a VC with an issuance or
+/// expiry date: both as timestamps, zero when unset.
+pub const VALIDITY_LEN: usize = 6 + 8 + 7 + 8;
+/// The holder section, after the validity section of a VC bound to a holder:
+/// the coordinates of the holder public key.
+pub const HOLDER_LEN: usize = 6 + 64;
+
+/// The length of the extra section of a VC spanning `chunks` leaves, after
+/// `fixed_len` bytes of fixed fields and optional sections: the 2-byte little
+/// endian length of the extra attributes, the attributes and zero padding.
+/// Single-chunk VCs have none, so their encoding is unchanged.
+pub fn extra_len(chunks: usize, fixed_len: usize) -> usize {
+    if chunks == 1 {
+        0
+    } else {
+        chunks * CHUNK_LEN - fixed_len - LEAF_OVERHEAD
+    }
+}
 
 impl VC {
     pub fn new(
@@ -35,9 +94,90 @@ impl VC {
             birth_date,
             edu_level,
             serial_no,
+            extra: vec![],
+            issued_at: None,
+            expires_at: None,
+            holder: None,
         }
     }
 
+    pub fn serial_no(&self) -> &[u8] {
+        &self.serial_no
+    }
+
+    /// Bind the VC to a holder, who must then prove knowledge of the secret key.
+    pub fn with_holder(mut self, holder: HolderPublicKey) -> Self {
+        self.holder = Some(holder);
+        self
+    }
+
+    pub fn holder(&self) -> Option<&HolderPublicKey> {
+        self.holder.as_ref()
+    }
+
+    /// The length of the fixed fields and the validity and holder sections.
+    fn fixed_len(&self) -> usize {
+        let validity_len = if self.has_validity() { VALIDITY_LEN } else { 0 };
+        let holder_len = if self.holder.is_some() { HOLDER_LEN } else { 0 };
+        VC_LEN + validity_len + holder_len
+    }
+
+    /// The VC is valid from `issued_at` and until the day before `expires_at`,
+    /// checked against the verifier's current date.
+    pub fn with_validity(
+        mut self,
+        issued_at: Option<NaiveDate>,
+        expires_at: Option<NaiveDate>,
+    ) -> Self {
+        self.issued_at = issued_at;
+        self.expires_at = expires_at;
+        self
+    }
+
+    /// Whether the encoding has a validity section, which needs a circuit
+    /// compiled with validity checks.
+    pub fn has_validity(&self) -> bool {
+        self.issued_at.is_some() || self.expires_at.is_some()
+    }
+
+    pub fn check_validity(&self, now: NaiveDate) -> Result<(), String> {
+        if let Some(issued_at) = self.issued_at {
+            if now < issued_at {
+                return Err(format!("VC is not valid before {}", issued_at));
+            }
+        }
+        if let Some(expires_at) = self.expires_at {
+            if now >= expires_at {
+                return Err(format!("VC expired on {}", expires_at));
+            }
+        }
+        Ok(())
+    }
+
+    /// Fails if `extra` is longer than [`EXTRA_MAX_LEN`].
+    pub fn with_extra(mut self, extra: Vec<u8>) -> Result<Self, String> {
+        check_extra_len(&extra)?;
+        self.extra = extra;
+        Ok(self)
+    }
+
+    pub fn extra(&self) -> &[u8] {
+        &self.extra
+    }
+
+    /// The number of leaves the encrypted VC spans, a power of two so that they
+    /// form one subtree of the storage flow.
+    pub fn chunks(&self) -> usize {
+        if self.extra.is_empty() {
+            return 1;
+        }
+        let mut chunks = 2;
+        while extra_len(chunks, self.fixed_len()) < 2 + self.extra.len() {
+            chunks *= 2;
+        }
+        chunks
+    }
+
     pub fn from_json(json: &str) -> serde_json::Result<Self> {
         serde_json::from_str(json)
     }
@@ -47,12 +187,11 @@ impl VC {
         keccak(&encoded_vc)
     }
 
-    pub fn plaintext(&self) -> [u8; VC_LEN + 32] {
-        let mut answer = [0u8; VC_LEN + 32];
-        let encoded_vc = self.encode();
-        answer[0..VC_LEN].copy_from_slice(&encoded_vc);
-        let digest = keccak(&encoded_vc);
-        answer[VC_LEN..VC_LEN + 32].copy_from_slice(&digest[..]);
+    /// The encoded VC followed by its hash.
+    pub fn plaintext(&self) -> Vec<u8> {
+        let mut answer = self.encode();
+        let digest = keccak(&answer);
+        answer.extend_from_slice(&digest[..]);
         answer
     }
 
@@ -62,7 +201,9 @@ impl VC {
         keccak(&file_data)
     }
 
-    pub fn encode(&self) -> [u8; VC_LEN] {
+    /// The fixed fields in `VC_LEN` bytes, then the validity, holder and extra
+    /// sections.
+    pub fn encode(&self) -> Vec<u8> {
         let name_padding = encode_fixed_length(&self.name, NAME_MAX_LEN).unwrap();
         let name = with_prefix("name", name_padding);
 
@@ -83,15 +224,88 @@ impl VC {
         assert!(edu_level.len() == 1 + 3);
         assert!(serial_no.len() == SERIAL_MAX_LEN + 6);
 
-        let encoded: Vec<u8> = [name, age, birth_date, edu_level, serial_no]
+        let mut encoded: Vec<u8> = [name, age, birth_date, edu_level, serial_no]
             .into_iter()
             .flatten()
             .collect();
+        assert!(encoded.len() == VC_LEN);
 
-        encoded.try_into().unwrap()
+        if self.has_validity() {
+            let timestamp = |date: Option<NaiveDate>| date.map_or(0, |x| date_to_timestamp(&x));
+            encoded.extend(with_prefix(
+                "issued",
+                timestamp(self.issued_at).to_le_bytes(),
+            ));
+            encoded.extend(with_prefix(
+                "expires",
+                timestamp(self.expires_at).to_le_bytes(),
+            ));
+        }
+
+        if let Some(holder) = &self.holder {
+            encoded.extend(with_prefix("holder", holder.to_bytes()));
+        }
+
+        let fixed_len = encoded.len();
+        assert!(fixed_len == self.fixed_len());
+        let extra_len = extra_len(self.chunks(), fixed_len);
+        if extra_len > 0 {
+            // `with_extra` and deserialization keep it within `EXTRA_MAX_LEN`.
+            let len = self.extra.len() as u16;
+            encoded.extend_from_slice(&len.to_le_bytes());
+            encoded.extend_from_slice(&self.extra);
+            encoded.resize(fixed_len + extra_len, 0);
+        }
+        encoded
     }
 }
 
+impl fmt::Debug for VC {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VC")
+            .field("chunks", &self.chunks())
+            .field("validity", &self.has_validity())
+            .field("holder", &self.holder)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Zeroize for VC {
+    fn zeroize(&mut self) {
+        self.name.zeroize();
+        self.age.zeroize();
+        self.birth_date = NaiveDate::MIN;
+        self.edu_level.zeroize();
+        self.serial_no.zeroize();
+        self.extra.zeroize();
+        self.issued_at = None;
+        self.expires_at = None;
+    }
+}
+
+impl Drop for VC {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+fn check_extra_len(extra: &[u8]) -> Result<(), String> {
+    if extra.len() > EXTRA_MAX_LEN {
+        return Err(format!(
+            "Extra attributes are {} bytes, at most {} are allowed",
+            extra.len(),
+            EXTRA_MAX_LEN
+        ));
+    }
+    Ok(())
+}
+
+fn deserialize_extra<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    let extra = hex_format::deserialize(deserializer)?;
+    check_extra_len(&extra).map_err(D::Error::custom)?;
+    Ok(extra)
+}
+
 fn with_prefix(prefix: &'static str, iter: impl IntoIterator<Item = u8>) -> Vec<u8> {
     prefix
         .as_bytes()
@@ -104,6 +318,7 @@ fn with_prefix(prefix: &'static str, iter: impl IntoIterator<Item = u8>) -> Vec<
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::holder::HolderKey;
     #[test]
     fn vc_encode_work() {
         let vc_json = r#"{"name": "Alice", "age": 25, "birth_date": "19991231", "edu_level": 4, "serial_no": "1234567890"}"#;
@@ -111,4 +326,95 @@ mod test {
         let encoded_vc = vc.encode();
         println!("encoded_vc: {:?}", encoded_vc);
     }
+
+    #[test]
+    fn debug_hides_claims() {
+        let vc_json = r#"{"name": "Alice", "age": 25, "birth_date": "19991231", "edu_level": 4, "serial_no": "1234567890"}"#;
+        let vc = VC::from_json(vc_json).unwrap();
+        let debug = format!("{:?}", vc);
+        assert!(!debug.contains("Alice"));
+        assert!(!debug.contains("1234567890"));
+    }
+
+    #[test]
+    fn extra_spans_chunks() {
+        let vc_json = r#"{"name": "Alice", "age": 25, "birth_date": "19991231", "edu_level": 4, "serial_no": "1234567890"}"#;
+        let vc = VC::from_json(vc_json).unwrap();
+        assert_eq!(vc.chunks(), 1);
+        assert_eq!(vc.encode().len(), VC_LEN);
+
+        let fits_two = extra_len(2, VC_LEN) - 2;
+        for (len, chunks) in [(1, 2), (fits_two, 2), (fits_two + 1, 4), (1000, 8)] {
+            let vc = vc.clone().with_extra(vec![7u8; len]).unwrap();
+            assert_eq!(vc.chunks(), chunks);
+            let encoded = vc.encode();
+            assert_eq!(encoded.len(), VC_LEN + extra_len(chunks, VC_LEN));
+            assert_eq!(vc.plaintext().len() + 16 + 1 + 16, chunks * CHUNK_LEN);
+            assert_eq!(encoded[VC_LEN..VC_LEN + 2], (len as u16).to_le_bytes());
+            assert!(encoded[VC_LEN + 2 + len..].iter().all(|x| *x == 0));
+        }
+
+        let json =
+            serde_json::to_string(&vc.clone().with_extra(b"addr".to_vec()).unwrap()).unwrap();
+        assert!(json.contains(r#""extra":"61646472""#));
+        assert_eq!(VC::from_json(&json).unwrap().extra(), b"addr");
+        assert!(!serde_json::to_string(&vc).unwrap().contains("extra"));
+
+        let too_long = vec![0u8; EXTRA_MAX_LEN + 1];
+        assert!(vc.clone().with_extra(too_long.clone()).is_err());
+        let mut value = serde_json::to_value(&vc).unwrap();
+        value["extra"] = hex::encode(&too_long).into();
+        assert!(VC::from_json(&value.to_string()).is_err());
+        let longest = vc.with_extra(vec![7u8; EXTRA_MAX_LEN]).unwrap();
+        assert_eq!(longest.encode()[VC_LEN..VC_LEN + 2], [0xff, 0xff]);
+    }
+
+    #[test]
+    fn validity_window() {
+        let date = |x| NaiveDate::parse_from_str(x, "%Y%m%d").unwrap();
+        let vc_json = r#"{"name": "Alice", "age": 25, "birth_date": "19991231", "edu_level": 4, "serial_no": "1234567890", "expires_at": "20300101"}"#;
+        let vc = VC::from_json(vc_json).unwrap();
+        assert!(vc.has_validity());
+        let encoded = vc.encode();
+        assert_eq!(encoded.len(), VC_LEN + VALIDITY_LEN);
+        assert_eq!(&encoded[VC_LEN..VC_LEN + 6], b"issued");
+        assert_eq!(encoded[VC_LEN + 6..VC_LEN + 14], [0u8; 8]);
+        assert_eq!(
+            encoded[VC_LEN + 21..],
+            date_to_timestamp(&date("20300101")).to_le_bytes()
+        );
+
+        assert!(vc.check_validity(date("20291231")).is_ok());
+        assert!(vc.check_validity(date("20300101")).is_err());
+
+        let vc = vc.with_validity(Some(date("20240101")), None);
+        assert!(vc.check_validity(date("20231231")).is_err());
+        assert!(vc.check_validity(date("20240101")).is_ok());
+        assert!(vc.check_validity(date("21000101")).is_ok());
+        let json = serde_json::to_string(&vc).unwrap();
+        assert!(json.contains(r#""issued_at":"20240101""#));
+        assert!(!json.contains("expires_at"));
+    }
+
+    #[test]
+    fn holder_section() {
+        let holder = HolderKey::from_seed(b"holder").public_key();
+        let vc_json = r#"{"name": "Alice", "age": 25, "birth_date": "19991231", "edu_level": 4, "serial_no": "1234567890"}"#;
+        let vc = VC::from_json(vc_json).unwrap().with_holder(holder);
+        let encoded = vc.encode();
+        assert_eq!(encoded.len(), VC_LEN + HOLDER_LEN);
+        assert_eq!(&encoded[VC_LEN..VC_LEN + 6], b"holder");
+        assert_eq!(encoded[VC_LEN + 6..], holder.to_bytes());
+
+        let vc = vc.with_extra(vec![1u8; 200]).unwrap();
+        assert_eq!(vc.chunks(), 2);
+        assert_eq!(
+            vc.encode().len(),
+            VC_LEN + HOLDER_LEN + extra_len(2, VC_LEN + HOLDER_LEN)
+        );
+
+        let json = serde_json::to_string(&vc).unwrap();
+        let decoded = VC::from_json(&json).unwrap();
+        assert_eq!(decoded.holder(), Some(&holder));
+    }
 }
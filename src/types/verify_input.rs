@@ -0,0 +1,105 @@
+use ark_ff::PrimeField;
+use chrono::NaiveDate;
+use keccak_hash::H256;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    ext::{ExtensionSignal, Extensions},
+    optional_date_format,
+};
+use crate::signal::{Signal, VerifyInput};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct VcVerifyInput {
+    pub(super) root: H256,
+    /// The roots of the second and following VCs of a multi-credential proof.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(super) other_roots: Vec<H256>,
+    pub(super) extensions: Extensions,
+    /// The current date for circuits with validity checks.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "optional_date_format"
+    )]
+    pub(super) now: Option<NaiveDate>,
+    /// The verifier's challenge for circuits bound to a session, see
+    /// [`crate::presentation::PresentationRequest::challenge`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(super) challenge: Option<H256>,
+}
+
+impl VcVerifyInput {
+    pub fn new(birthdate_threshold: NaiveDate, root: H256) -> Self {
+        let extensions = vec![ExtensionSignal::Date(birthdate_threshold)]
+            .try_into()
+            .unwrap();
+        Self {
+            extensions,
+            root,
+            other_roots: vec![],
+            now: None,
+            challenge: None,
+        }
+    }
+
+    /// Check VCs with an issuance or expiry date against `now`.
+    pub fn with_now(mut self, now: NaiveDate) -> Self {
+        self.now = Some(now);
+        self
+    }
+
+    pub fn now(&self) -> Option<NaiveDate> {
+        self.now
+    }
+
+    pub fn extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+
+    pub fn with_challenge(mut self, challenge: H256) -> Self {
+        self.challenge = Some(challenge);
+        self
+    }
+
+    pub fn challenge(&self) -> Option<H256> {
+        self.challenge
+    }
+
+    /// The public input of `VerifyMultiVC`, with the root of the first VC and
+    /// those of the others in order.
+    pub fn new_multi(birthdate_threshold: NaiveDate, root: H256, other_roots: Vec<H256>) -> Self {
+        Self {
+            other_roots,
+            ..Self::new(birthdate_threshold, root)
+        }
+    }
+
+    pub fn roots(&self) -> Vec<H256> {
+        std::iter::once(self.root)
+            .chain(self.other_roots.iter().cloned())
+            .collect()
+    }
+
+    pub fn to_public_inputs<F: PrimeField>(&self) -> Vec<F> {
+        let mut inputs: Vec<F> = self
+            .roots()
+            .iter()
+            .flat_map(|root| root.to_signal_fr::<F>())
+            .collect();
+        inputs.extend(self.extensions.to_signal_fr::<F>());
+        if let Some(now) = self.now {
+            inputs.extend(now.to_signal_fr::<F>());
+        }
+        if let Some(challenge) = self.challenge {
+            inputs.extend(challenge.to_signal_fr::<F>());
+        }
+        inputs
+    }
+}
+
+impl<F: PrimeField> VerifyInput<F> for VcVerifyInput {
+    fn to_verify_input(&self) -> Vec<F> {
+        self.to_public_inputs()
+    }
+}
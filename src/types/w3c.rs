@@ -0,0 +1,304 @@
+//! Import of W3C Verifiable Credentials (Data Model 1.1 and 2.0) into the
+//! circuit encoding. Claims are picked from the JSON document with JSON
+//! pointers, e.g. `/credentialSubject/name`, so each issuer's vocabulary is a
+//! [`W3cMapping`] rather than code.
+
+use chrono::{DateTime, NaiveDate};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{
+    ext::{ExtensionSignal, Extensions},
+    vc::{NAME_MAX_LEN, SERIAL_MAX_LEN},
+    VC,
+};
+use crate::utils::encode_fixed_length;
+
+/// How the claim selected by `serial_no` becomes the serial number bytes.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SerialEncoding {
+    /// The UTF-8 bytes of the string.
+    #[default]
+    Utf8,
+    /// A hex string, with or without `0x`.
+    Hex,
+    /// A UUID, with or without the `urn:uuid:` prefix, as its 16 bytes.
+    Uuid,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExtensionKind {
+    Date,
+    Number,
+}
+
+/// A claim passed to the circuit as an extension signal.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ExtensionMapping {
+    pub pointer: String,
+    pub kind: ExtensionKind,
+}
+
+/// Where the fields of a [`VC`] are found in a W3C credential. Pointers are
+/// RFC 6901 JSON pointers from the root of the document.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct W3cMapping {
+    pub name: String,
+    pub birth_date: String,
+    /// Without a pointer, the age is computed from the birth date on the
+    /// issuance date.
+    #[serde(default)]
+    pub age: Option<String>,
+    pub edu_level: String,
+    /// Defaults to the credential `id`.
+    #[serde(default = "default_serial_no")]
+    pub serial_no: String,
+    #[serde(default)]
+    pub serial_encoding: SerialEncoding,
+    /// Encode `issuanceDate`/`validFrom` and `expirationDate`/`validUntil` as
+    /// the validity section, for circuits compiled with validity checks.
+    #[serde(default)]
+    pub validity: bool,
+    #[serde(default)]
+    pub extensions: Vec<ExtensionMapping>,
+}
+
+fn default_serial_no() -> String {
+    "/id".to_string()
+}
+
+/// A credential in the circuit encoding, see [`VC::encode`].
+#[derive(Clone, Debug)]
+pub struct MappedVc {
+    pub vc: VC,
+    pub extensions: Extensions,
+}
+
+impl MappedVc {
+    pub fn encode(&self) -> Vec<u8> {
+        self.vc.encode()
+    }
+}
+
+impl W3cMapping {
+    pub fn map_json(&self, json: &str) -> Result<MappedVc, String> {
+        let document: Value =
+            serde_json::from_str(json).map_err(|e| format!("Invalid credential JSON: {}", e))?;
+        self.map(&document)
+    }
+
+    /// Map a credential document, rejecting claims that do not fit the
+    /// circuit's field sizes rather than truncating them.
+    pub fn map(&self, document: &Value) -> Result<MappedVc, String> {
+        if document.get("credentialSubject").is_none() {
+            return Err("Not a W3C credential: missing credentialSubject".to_string());
+        }
+
+        let name = string(document, &self.name)?;
+        encode_fixed_length(name, NAME_MAX_LEN)
+            .map_err(|_| format!("{}: longer than {} bytes", self.name, NAME_MAX_LEN))?;
+
+        let birth_date = date(document, &self.birth_date)?;
+        let issued_at = optional_date(document, &["/validFrom", "/issuanceDate"])?;
+        let expires_at = optional_date(document, &["/validUntil", "/expirationDate"])?;
+
+        let age = match &self.age {
+            Some(pointer) => byte(document, pointer)?,
+            None => {
+                let issued_at =
+                    issued_at.ok_or("Cannot compute the age without an issuance date")?;
+                let age = issued_at
+                    .years_since(birth_date)
+                    .ok_or("Birth date is after the issuance date")?;
+                u8::try_from(age).map_err(|_| "Age does not fit in a byte".to_string())?
+            }
+        };
+        let edu_level = byte(document, &self.edu_level)?;
+
+        let serial_no = self
+            .serial_encoding
+            .decode(string(document, &self.serial_no)?)?;
+        if serial_no.len() > SERIAL_MAX_LEN {
+            return Err(format!(
+                "{}: longer than {} bytes",
+                self.serial_no, SERIAL_MAX_LEN
+            ));
+        }
+
+        let mut vc = VC::new(name.to_string(), age, birth_date, edu_level, serial_no);
+        if self.validity {
+            if issued_at.is_none() && expires_at.is_none() {
+                return Err("Credential has neither an issuance nor an expiration date".to_string());
+            }
+            vc = vc.with_validity(issued_at, expires_at);
+        }
+
+        let extensions = self
+            .extensions
+            .iter()
+            .map(|ext| match ext.kind {
+                ExtensionKind::Date => date(document, &ext.pointer).map(ExtensionSignal::Date),
+                ExtensionKind::Number => {
+                    let value = claim(document, &ext.pointer)?;
+                    value
+                        .as_u64()
+                        .map(|x| ExtensionSignal::Number(x.into()))
+                        .ok_or(format!("{}: not a non-negative integer", ext.pointer))
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?
+            .try_into()
+            .map_err(|e: &str| e.to_string())?;
+
+        Ok(MappedVc { vc, extensions })
+    }
+}
+
+impl SerialEncoding {
+    fn decode(&self, value: &str) -> Result<Vec<u8>, String> {
+        match self {
+            SerialEncoding::Utf8 => Ok(value.as_bytes().to_vec()),
+            SerialEncoding::Hex => hex::decode(value.trim_start_matches("0x"))
+                .map_err(|e| format!("Invalid hex serial number: {}", e)),
+            SerialEncoding::Uuid => {
+                let uuid = value.trim_start_matches("urn:uuid:").replace('-', "");
+                match hex::decode(&uuid) {
+                    Ok(bytes) if bytes.len() == 16 => Ok(bytes),
+                    _ => Err(format!("Invalid UUID serial number: {}", value)),
+                }
+            }
+        }
+    }
+}
+
+fn claim<'a>(document: &'a Value, pointer: &str) -> Result<&'a Value, String> {
+    document
+        .pointer(pointer)
+        .ok_or(format!("{}: missing claim", pointer))
+}
+
+fn string<'a>(document: &'a Value, pointer: &str) -> Result<&'a str, String> {
+    claim(document, pointer)?
+        .as_str()
+        .ok_or(format!("{}: not a string", pointer))
+}
+
+fn byte(document: &Value, pointer: &str) -> Result<u8, String> {
+    claim(document, pointer)?
+        .as_u64()
+        .and_then(|x| u8::try_from(x).ok())
+        .ok_or(format!("{}: not an integer from 0 to 255", pointer))
+}
+
+/// Dates are either `YYYY-MM-DD` or an RFC 3339 date-time, whose date in its
+/// own offset is taken.
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .or_else(|| {
+            DateTime::parse_from_rfc3339(value)
+                .ok()
+                .map(|x| x.date_naive())
+        })
+}
+
+fn date(document: &Value, pointer: &str) -> Result<NaiveDate, String> {
+    parse_date(string(document, pointer)?).ok_or(format!("{}: not a date", pointer))
+}
+
+/// The first of `pointers` present in the document.
+fn optional_date(document: &Value, pointers: &[&str]) -> Result<Option<NaiveDate>, String> {
+    match pointers.iter().find(|x| document.pointer(x).is_some()) {
+        Some(pointer) => date(document, pointer).map(Some),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn credential() -> Value {
+        serde_json::json!({
+            "@context": ["https://www.w3.org/2018/credentials/v1"],
+            "id": "urn:uuid:3978344f-8596-4c3a-a978-8fcaba3903c5",
+            "type": ["VerifiableCredential", "UniversityDegreeCredential"],
+            "issuer": "did:example:76e12ec712ebc6f1c221ebfeb1f",
+            "issuanceDate": "2024-01-01T19:23:24Z",
+            "expirationDate": "2030-01-01T00:00:00Z",
+            "credentialSubject": {
+                "id": "did:example:ebfeb1f712ebc6f1c276e12ec21",
+                "name": "Alice",
+                "birthDate": "1999-12-31",
+                "degree": {"level": 4, "graduated": "2022-06-30"}
+            }
+        })
+    }
+
+    fn mapping() -> W3cMapping {
+        serde_json::from_str(
+            r#"{
+                "name": "/credentialSubject/name",
+                "birth_date": "/credentialSubject/birthDate",
+                "edu_level": "/credentialSubject/degree/level",
+                "serial_encoding": "uuid",
+                "validity": true,
+                "extensions": [{"pointer": "/credentialSubject/degree/graduated", "kind": "date"}]
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn map_w3c_credential() {
+        let mapped = mapping().map(&credential()).unwrap();
+        let date = |x| NaiveDate::parse_from_str(x, "%Y%m%d").unwrap();
+        let expected = VC::new(
+            "Alice".to_string(),
+            24,
+            date("19991231"),
+            4,
+            hex::decode("3978344f85964c3aa9788fcaba3903c5").unwrap(),
+        )
+        .with_validity(Some(date("20240101")), Some(date("20300101")));
+        assert_eq!(mapped.encode(), expected.encode());
+        assert_eq!(
+            mapped.extensions.to_vec(),
+            vec![ExtensionSignal::Date(date("20220630"))]
+        );
+
+        let mapping = W3cMapping {
+            validity: false,
+            ..mapping()
+        };
+        assert!(!mapping.map(&credential()).unwrap().vc.has_validity());
+    }
+
+    #[test]
+    fn reject_oversized_claims() {
+        let mut document = credential();
+        document["credentialSubject"]["name"] = "Alice Wonderland-Liddell".into();
+        assert!(mapping().map(&document).is_err());
+
+        let mut document = credential();
+        document["credentialSubject"]["degree"]["level"] = 256.into();
+        assert!(mapping().map(&document).is_err());
+
+        let utf8 = W3cMapping {
+            serial_encoding: SerialEncoding::Utf8,
+            serial_no: "/issuer".to_string(),
+            ..mapping()
+        };
+        assert!(utf8.map(&credential()).is_err());
+
+        let mut document = credential();
+        document
+            .as_object_mut()
+            .unwrap()
+            .remove("credentialSubject");
+        let err = mapping().map(&document).unwrap_err();
+        assert_eq!(err, "Not a W3C credential: missing credentialSubject");
+    }
+}
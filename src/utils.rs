@@ -1,7 +1,17 @@
+use std::io;
+
+#[cfg(feature = "prover")]
+use ark_bn254::Fr;
+#[cfg(feature = "prover")]
+use ark_ff::{BigInteger, PrimeField};
+use ark_serialize::SerializationError;
 use chrono::NaiveDate;
+#[cfg(feature = "prover")]
 use keccak_hash::H256;
-use tiny_keccak::Hasher;
-use tiny_keccak::Keccak;
+#[cfg(feature = "prover")]
+use light_poseidon::{Poseidon, PoseidonHasher};
+#[cfg(feature = "prover")]
+use tiny_keccak::{Hasher, Keccak};
 
 pub fn date_to_timestamp(date: &NaiveDate) -> u64 {
     date.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp() as u64
@@ -20,6 +30,7 @@ pub fn encode_fixed_length(input: &str, length: usize) -> Result<Vec<u8>, &'stat
     Ok(result)
 }
 
+#[cfg(feature = "prover")]
 pub fn keccak_tuple(x: H256, y: H256) -> H256 {
     let mut keccak256 = Keccak::v256();
     keccak256.update(x.as_ref());
@@ -28,3 +39,75 @@ pub fn keccak_tuple(x: H256, y: H256) -> H256 {
     keccak256.finalize(&mut result.0);
     result
 }
+
+// A hash is a BN254 field element stored little endian, so its two 128-bit
+// signal halves are the same as for a keccak hash.
+#[cfg(feature = "prover")]
+fn hash_to_fr(x: H256) -> Fr {
+    Fr::from_le_bytes_mod_order(x.as_bytes())
+}
+
+#[cfg(feature = "prover")]
+fn fr_to_hash(x: Fr) -> H256 {
+    H256::from_slice(&x.into_bigint().to_bytes_le())
+}
+
+#[cfg(feature = "prover")]
+fn poseidon(inputs: &[Fr]) -> H256 {
+    let mut hasher = Poseidon::<Fr>::new_circom(inputs.len()).unwrap();
+    fr_to_hash(hasher.hash(inputs).unwrap())
+}
+
+/// The circomlib Poseidon hash of two nodes, as `HashLeftRightPoseidon`.
+#[cfg(feature = "prover")]
+pub fn poseidon_tuple(x: H256, y: H256) -> H256 {
+    poseidon(&[hash_to_fr(x), hash_to_fr(y)])
+}
+
+/// Bytes per field element when packing a leaf for Poseidon.
+#[cfg(feature = "prover")]
+pub const POSEIDON_CHUNK: usize = 31;
+
+/// The circomlib Poseidon hash of a leaf split into little endian 31-byte
+/// chunks, as `HashLeafPoseidon`.
+#[cfg(feature = "prover")]
+pub fn poseidon_leaf(leaf: &[u8; 256]) -> H256 {
+    let chunks: Vec<Fr> = leaf
+        .chunks(POSEIDON_CHUNK)
+        .map(Fr::from_le_bytes_mod_order)
+        .collect();
+    poseidon(&chunks)
+}
+
+pub fn invalid_data(msg: impl Into<String>) -> SerializationError {
+    SerializationError::IoError(io::Error::new(io::ErrorKind::InvalidData, msg.into()))
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn poseidon_matches_circomlib() {
+        // poseidon([1, 2]) from circomlibjs.
+        let expected = "115cc0f5e7d690413df64c6b9662e9cf2a3617f2743245519e19607a4417189a";
+        let mut expected = hex::decode(expected).unwrap();
+        expected.reverse();
+
+        let one = fr_to_hash(Fr::from(1u64));
+        let two = fr_to_hash(Fr::from(2u64));
+        assert_eq!(poseidon_tuple(one, two), H256::from_slice(&expected));
+        assert_ne!(poseidon_tuple(two, one), H256::from_slice(&expected));
+    }
+
+    #[test]
+    fn poseidon_leaf_packs_every_byte() {
+        let leaf = [0xabu8; 256];
+        let hash = poseidon_leaf(&leaf);
+        for position in [0, 30, 31, 255] {
+            let mut other = leaf;
+            other[position] ^= 1;
+            assert_ne!(poseidon_leaf(&other), hash);
+        }
+    }
+}
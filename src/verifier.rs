@@ -0,0 +1,149 @@
+//! Proof verification, everything a relying party needs. This module builds
+//! without the `prover` feature, so verifier deployments carry neither circom,
+//! the WASM runtime nor AES. Load the key with
+//! [`crate::params::load_verifying_key`] and the public inputs as
+//! [`crate::types::VcVerifyInput`].
+
+use ark_ec::pairing::Pairing;
+use ark_groth16::{Groth16, PreparedVerifyingKey, Proof, VerifyingKey};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use rand::Rng;
+
+use crate::signal::VerifyInput;
+
+/// Proofs are exchanged as the compressed arkworks encoding, 128 bytes on
+/// BN254 and 192 on BLS12-381.
+pub fn proof_to_bytes<E: Pairing>(proof: &Proof<E>) -> Vec<u8> {
+    let mut raw = Vec::with_capacity(proof.compressed_size());
+    proof.serialize_compressed(&mut raw).unwrap();
+    raw
+}
+
+pub fn proof_from_bytes<E: Pairing>(raw: &[u8]) -> Result<Proof<E>, String> {
+    Proof::deserialize_compressed(raw).map_err(|e| format!("Invalid proof: {:?}", e))
+}
+
+pub fn verify<E: Pairing>(
+    vk: &PreparedVerifyingKey<E>,
+    proof: &Proof<E>,
+    public_inputs: &impl VerifyInput<E::ScalarField>,
+) -> Result<bool, String> {
+    Groth16::<E>::verify_proof(vk, proof, &public_inputs.to_verify_input())
+        .map_err(|e| format!("Cannot verify: {:?}", e))
+}
+
+/// A fresh proof of the same statement, so a holder who shows one proof to
+/// several verifiers cannot be linked by it. It verifies against `vk` for the
+/// same public inputs and is distributed like a newly computed proof, at the
+/// cost of a few curve multiplications instead of a prove.
+pub fn rerandomize_proof<E: Pairing, R: Rng>(
+    vk: &VerifyingKey<E>,
+    proof: &Proof<E>,
+    rng: &mut R,
+) -> Proof<E> {
+    Groth16::<E>::rerandomize_proof(vk, proof, rng)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{params::read_verifying_key, types::VcVerifyInput};
+    use ark_bn254::Bn254;
+
+    // The fixture proves a synthetic circuit that only exposes
+    // `Sample::public_input()`, with the same public inputs as check_vc. It is
+    // written by `write_fixture`.
+    #[test]
+    fn verify_fixture() {
+        let (_, vk) =
+            read_verifying_key(&include_bytes!("../tests/fixtures/verifier.vk")[..]).unwrap();
+        let raw_proof = include_bytes!("../tests/fixtures/verifier.proof");
+        let proof = proof_from_bytes::<Bn254>(raw_proof).unwrap();
+        assert_eq!(proof_to_bytes(&proof), raw_proof);
+
+        let mut input: serde_json::Value =
+            serde_json::from_str(include_str!("../tests/fixtures/verifier_input.json")).unwrap();
+        let public_input: VcVerifyInput = serde_json::from_value(input.clone()).unwrap();
+        assert!(verify(&vk, &proof, &public_input).unwrap());
+
+        let mut rng = rand::thread_rng();
+        let rerandomized = rerandomize_proof(&vk.vk, &proof, &mut rng);
+        assert_ne!(proof_to_bytes(&rerandomized), raw_proof);
+        assert!(verify(&vk, &rerandomized, &public_input).unwrap());
+        let again = rerandomize_proof(&vk.vk, &rerandomized, &mut rng);
+        assert_ne!(again, rerandomized);
+        assert!(verify(&vk, &again, &public_input).unwrap());
+
+        input["extensions"][0]["date"] = "20000305".into();
+        let public_input: VcVerifyInput = serde_json::from_value(input).unwrap();
+        assert!(!verify(&vk, &proof, &public_input).unwrap());
+        assert!(!verify(&vk, &again, &public_input).unwrap());
+    }
+
+    /// Rewrite `tests/fixtures/verifier.*`, e.g. after the `.vk` format
+    /// changed: `cargo test --lib write_fixture -- --ignored`. The setup and
+    /// proof use the deterministic `test_rng`.
+    #[cfg(feature = "prover")]
+    #[test]
+    #[ignore = "rewrites the fixtures"]
+    fn write_fixture() {
+        use crate::{
+            groth16::Groth16,
+            params::{write_verifying_key, CircuitInfo},
+            sample::Sample,
+        };
+        use ark_bn254::Fr;
+        use ark_circom::{circom::R1CS, CircomCircuit};
+        use ark_ff::One;
+
+        let input = Sample::public_input();
+        let public = input.to_public_inputs::<Fr>();
+        let n = public.len();
+        // Wires are [1, p_1, .., p_n, w]: each p_i * 1 = p_i, and w * 1 = p_1
+        // so the circuit has a witness.
+        let mut constraints: Vec<_> = (1..=n)
+            .map(|i| {
+                (
+                    vec![(i, Fr::one())],
+                    vec![(0, Fr::one())],
+                    vec![(i, Fr::one())],
+                )
+            })
+            .collect();
+        constraints.push((
+            vec![(n + 1, Fr::one())],
+            vec![(0, Fr::one())],
+            vec![(1, Fr::one())],
+        ));
+        let r1cs = R1CS::<Bn254> {
+            num_inputs: n + 1,
+            num_aux: 1,
+            num_variables: n + 2,
+            constraints,
+            wire_mapping: None,
+        };
+
+        let mut rng = ark_std::test_rng();
+        let circuit = CircomCircuit {
+            r1cs: r1cs.clone(),
+            witness: None,
+        };
+        let pk = Groth16::generate_random_parameters_with_reduction(circuit, &mut rng).unwrap();
+        let mut witness = vec![Fr::one()];
+        witness.extend(public.iter().cloned());
+        witness.push(public[0]);
+        let circuit = CircomCircuit {
+            r1cs,
+            witness: Some(witness),
+        };
+        let proof = Groth16::create_random_proof_with_reduction(circuit, &pk, &mut rng).unwrap();
+
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+        let mut vk = vec![];
+        write_verifying_key(&pk.vk, &CircuitInfo::default(), &mut vk).unwrap();
+        std::fs::write(dir.join("verifier.vk"), vk).unwrap();
+        std::fs::write(dir.join("verifier.proof"), proof_to_bytes(&proof)).unwrap();
+        let json = serde_json::to_string_pretty(&input).unwrap();
+        std::fs::write(dir.join("verifier_input.json"), json).unwrap();
+    }
+}
@@ -0,0 +1,370 @@
+//! A holder's wallet: one password-protected file with the VCs, their keys and
+//! IVs, where their leaves are stored and the latest merkle proof of each, so
+//! proving takes a credential name instead of a hand-written prove input.
+//!
+//! The file is JSON with the key derivation parameters in the clear and the
+//! credentials encrypted with AES-256-GCM under a key derived from the
+//! password with Argon2id. Every save uses a fresh nonce.
+
+use std::{collections::BTreeMap, path::PathBuf};
+
+use aes_gcm::{aead::AeadInPlace, Aes256Gcm, KeyInit};
+use argon2::{Algorithm, Argon2, Params, Version};
+use chrono::NaiveDate;
+use keccak_hash::H256;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroize;
+
+use crate::{
+    holder::HolderKey,
+    issuance::HolderBundle,
+    signal::ProveInput,
+    types::{expose_secrets, ByteArray, HashMode, Secret, VcProveInput},
+};
+
+const WALLET_VERSION: u32 = 1;
+const ASSOCIATED_DATA: &[u8] = b"vc-prove wallet v1";
+
+/// Argon2id costs, stored in the wallet file so they can be raised later.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KdfParams {
+    /// Memory in KiB.
+    pub m_cost: u32,
+    pub t_cost: u32,
+    pub p_cost: u32,
+}
+
+impl Default for KdfParams {
+    /// The OWASP recommendation for Argon2id: 19 MiB, two passes.
+    fn default() -> Self {
+        Self {
+            m_cost: 19 * 1024,
+            t_cost: 2,
+            p_cost: 1,
+        }
+    }
+}
+
+impl KdfParams {
+    fn derive(&self, password: &str, salt: &[u8]) -> Result<Secret<ByteArray<32>>, String> {
+        let params = Params::new(self.m_cost, self.t_cost, self.p_cost, Some(32))
+            .map_err(|e| format!("Invalid key derivation parameters: {}", e))?;
+        let mut key = [0u8; 32];
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(password.as_bytes(), salt, &mut key)
+            .map_err(|e| format!("Cannot derive wallet key: {}", e))?;
+        Ok(Secret::new(ByteArray::new(key)))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct WalletFile {
+    version: u32,
+    kdf: KdfParams,
+    salt: ByteArray<16>,
+    nonce: ByteArray<12>,
+    #[serde(with = "crate::types::hex_format")]
+    ciphertext: Vec<u8>,
+}
+
+/// A stored credential.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Credential {
+    pub bundle: HolderBundle,
+    #[serde(default)]
+    pub hash_mode: HashMode,
+    /// The position of the leaf, or of the subtree of a VC spanning several
+    /// leaves, in the storage flow.
+    pub path_index: usize,
+    pub merkle_proof: Vec<H256>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub holder_key: Option<Secret<HolderKey>>,
+}
+
+impl Credential {
+    pub fn new(bundle: HolderBundle, path_index: usize, merkle_proof: Vec<H256>) -> Self {
+        Self {
+            bundle,
+            hash_mode: HashMode::Keccak,
+            path_index,
+            merkle_proof,
+            holder_key: None,
+        }
+    }
+
+    pub fn with_hash_mode(mut self, hash_mode: HashMode) -> Self {
+        self.hash_mode = hash_mode;
+        self
+    }
+
+    /// For VCs bound to a holder, see [`crate::types::VC::with_holder`].
+    pub fn with_holder_key(mut self, holder_key: HolderKey) -> Self {
+        self.holder_key = Some(holder_key.into());
+        self
+    }
+
+    fn prove_input_with(
+        &self,
+        birthdate_threshold: NaiveDate,
+        proof: Vec<H256>,
+    ) -> Result<VcProveInput, String> {
+        let input = self
+            .bundle
+            .to_prove_input(birthdate_threshold, proof, self.path_index)?
+            .with_hash_mode(self.hash_mode);
+        Ok(match &self.holder_key {
+            Some(key) => input.with_holder_key((**key).clone()),
+            None => input,
+        })
+    }
+
+    pub fn prove_input(&self, birthdate_threshold: NaiveDate) -> Result<VcProveInput, String> {
+        self.prove_input_with(birthdate_threshold, self.merkle_proof.clone())
+    }
+
+    /// The root the stored merkle proof leads to.
+    pub fn root(&self) -> Result<H256, String> {
+        Ok(self.prove_input(NaiveDate::MIN)?.merkle_root())
+    }
+}
+
+pub struct Wallet {
+    path: PathBuf,
+    kdf: KdfParams,
+    salt: ByteArray<16>,
+    key: Secret<ByteArray<32>>,
+    credentials: BTreeMap<String, Credential>,
+}
+
+impl Wallet {
+    /// Create an empty wallet at `path`, failing if the file exists.
+    pub fn create(path: &PathBuf, password: &str) -> Result<Self, String> {
+        Self::create_with(path, password, KdfParams::default())
+    }
+
+    pub fn create_with(path: &PathBuf, password: &str, kdf: KdfParams) -> Result<Self, String> {
+        if path.exists() {
+            return Err(format!("Wallet {} already exists", path.display()));
+        }
+        let salt = ByteArray::new(rand::thread_rng().gen());
+        let wallet = Self {
+            path: path.clone(),
+            kdf,
+            key: kdf.derive(password, salt.as_ref())?,
+            salt,
+            credentials: BTreeMap::new(),
+        };
+        wallet.save()?;
+        Ok(wallet)
+    }
+
+    pub fn open(path: &PathBuf, password: &str) -> Result<Self, String> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| format!("Cannot read wallet {}: {}", path.display(), e))?;
+        let file: WalletFile =
+            serde_json::from_str(&json).map_err(|e| format!("Invalid wallet file: {}", e))?;
+        if file.version != WALLET_VERSION {
+            return Err(format!("Unsupported wallet version {}", file.version));
+        }
+
+        let key = file.kdf.derive(password, file.salt.as_ref())?;
+        let split = file
+            .ciphertext
+            .len()
+            .checked_sub(16)
+            .ok_or("Truncated wallet file")?;
+        let (ciphertext, tag) = file.ciphertext.split_at(split);
+        let mut plaintext = ciphertext.to_vec();
+        Aes256Gcm::new(AsRef::<[u8; 32]>::as_ref(&*key).into())
+            .decrypt_in_place_detached(
+                AsRef::<[u8; 12]>::as_ref(&file.nonce).into(),
+                ASSOCIATED_DATA,
+                &mut plaintext,
+                tag.into(),
+            )
+            .map_err(|_| "Wrong password or corrupted wallet".to_string())?;
+        let plaintext = Secret::new(plaintext);
+
+        let credentials = serde_json::from_slice(&plaintext)
+            .map_err(|e| format!("Invalid wallet content: {}", e))?;
+        Ok(Self {
+            path: path.clone(),
+            kdf: file.kdf,
+            salt: file.salt,
+            key,
+            credentials,
+        })
+    }
+
+    /// Encrypt the credentials and replace the wallet file.
+    pub fn save(&self) -> Result<(), String> {
+        let mut buffer = expose_secrets(|| serde_json::to_vec(&self.credentials))
+            .map_err(|e| format!("Cannot encode wallet: {}", e))?;
+        let nonce: [u8; 12] = rand::thread_rng().gen();
+        let result = Aes256Gcm::new(AsRef::<[u8; 32]>::as_ref(&*self.key).into())
+            .encrypt_in_place_detached((&nonce).into(), ASSOCIATED_DATA, &mut buffer);
+        let tag = match result {
+            Ok(tag) => tag,
+            Err(e) => {
+                buffer.zeroize();
+                return Err(format!("Cannot encrypt wallet: {:?}", e));
+            }
+        };
+        buffer.extend_from_slice(&tag);
+
+        let file = WalletFile {
+            version: WALLET_VERSION,
+            kdf: self.kdf,
+            salt: self.salt.clone(),
+            nonce: ByteArray::new(nonce),
+            ciphertext: buffer,
+        };
+        // Write aside and rename, so a crash never leaves a truncated wallet.
+        let temp = self.path.with_extension("tmp");
+        std::fs::write(&temp, serde_json::to_vec_pretty(&file).unwrap())
+            .and_then(|_| std::fs::rename(&temp, &self.path))
+            .map_err(|e| format!("Cannot write wallet {}: {}", self.path.display(), e))
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.credentials.keys().map(String::as_str)
+    }
+
+    pub fn get(&self, name: &str) -> Result<&Credential, String> {
+        self.credentials
+            .get(name)
+            .ok_or(format!("No credential named {}", name))
+    }
+
+    /// Add or replace a credential. Call [`Wallet::save`] to persist changes.
+    pub fn insert(&mut self, name: &str, credential: Credential) {
+        self.credentials.insert(name.to_string(), credential);
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Credential> {
+        self.credentials.remove(name)
+    }
+
+    /// Replace the merkle proof of a credential after the flow grew, checking
+    /// that it leads from the stored leaf to the new `root`.
+    pub fn refresh_proof(
+        &mut self,
+        name: &str,
+        merkle_proof: Vec<H256>,
+        root: H256,
+    ) -> Result<(), String> {
+        let credential = self.get(name)?;
+        let input = credential.prove_input_with(NaiveDate::MIN, merkle_proof.clone())?;
+        if input.merkle_root() != root {
+            return Err(format!("The proof of {} does not lead to the root", name));
+        }
+        self.credentials.get_mut(name).unwrap().merkle_proof = merkle_proof;
+        Ok(())
+    }
+
+    /// The prove input of a credential, checked like [`ProveInput::check`]
+    /// against `now` when the VC has validity dates.
+    pub fn prove_input(
+        &self,
+        name: &str,
+        birthdate_threshold: NaiveDate,
+        now: Option<NaiveDate>,
+    ) -> Result<VcProveInput, String> {
+        let mut input = self.get(name)?.prove_input(birthdate_threshold)?;
+        if let Some(now) = now {
+            input = input.with_now(now);
+        }
+        input.check()?;
+        Ok(input)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        issuance::{Issuer, IvRegistry},
+        sample::Sample,
+    };
+    use rand::thread_rng;
+
+    const CHEAP: KdfParams = KdfParams {
+        m_cost: 64,
+        t_cost: 1,
+        p_cost: 1,
+    };
+
+    fn wallet_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("{}-{}.wallet", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn wallet_roundtrip() {
+        let path = wallet_path("roundtrip");
+        let issued = Issuer::new(IvRegistry::in_memory())
+            .issue(&mut thread_rng(), Sample::vc())
+            .unwrap();
+        let credential = Credential::new(issued.bundle, 0, Sample::merkle_path(3));
+        let root = credential.root().unwrap();
+
+        let mut wallet = Wallet::create_with(&path, "correct horse", CHEAP).unwrap();
+        assert!(Wallet::create_with(&path, "correct horse", CHEAP).is_err());
+        wallet.insert("id", credential);
+        wallet.save().unwrap();
+
+        let raw = std::fs::read_to_string(&path).unwrap();
+        assert!(!raw.contains("Alice"));
+        assert!(Wallet::open(&path, "wrong password").is_err());
+
+        let mut wallet = Wallet::open(&path, "correct horse").unwrap();
+        assert_eq!(wallet.names().collect::<Vec<_>>(), vec!["id"]);
+        let input = wallet.prove_input("id", Sample::threshold(), None).unwrap();
+        assert_eq!(input.merkle_root(), root);
+        assert_eq!(
+            input.leaf(),
+            wallet.get("id").unwrap().bundle.leaf().unwrap()
+        );
+        assert!(wallet
+            .prove_input("diploma", Sample::threshold(), None)
+            .is_err());
+
+        // The flow grew: one more level above the old root.
+        let mut proof = Sample::merkle_path(3);
+        proof.push(H256::repeat_byte(7));
+        let new_root = HashMode::Keccak.hash_tuple(root, H256::repeat_byte(7));
+        assert!(wallet
+            .refresh_proof("id", proof.clone(), H256::zero())
+            .is_err());
+        wallet.refresh_proof("id", proof, new_root).unwrap();
+        wallet.save().unwrap();
+        let wallet = Wallet::open(&path, "correct horse").unwrap();
+        assert_eq!(wallet.get("id").unwrap().root().unwrap(), new_root);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn wallet_checks_validity() {
+        let path = wallet_path("validity");
+        let date = |x| NaiveDate::parse_from_str(x, "%Y%m%d").unwrap();
+        let vc = Sample::vc().with_validity(None, Some(date("20300101")));
+        let issued = Issuer::new(IvRegistry::in_memory())
+            .issue(&mut thread_rng(), vc)
+            .unwrap();
+
+        let mut wallet = Wallet::create_with(&path, "pw", CHEAP).unwrap();
+        wallet.insert("id", Credential::new(issued.bundle, 0, vec![]));
+        let threshold = Sample::threshold();
+        assert!(wallet.prove_input("id", threshold, None).is_err());
+        assert!(wallet
+            .prove_input("id", threshold, Some(date("20300101")))
+            .is_err());
+        assert!(wallet
+            .prove_input("id", threshold, Some(date("20291231")))
+            .is_ok());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
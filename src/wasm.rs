@@ -0,0 +1,44 @@
+//! JavaScript bindings of the verifier for browsers and light clients. Build
+//! for `wasm32-unknown-unknown` with `--no-default-features --features wasm`.
+
+use ark_bn254::Bn254;
+use ark_groth16::PreparedVerifyingKey;
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    params::read_verifying_key,
+    types::VcVerifyInput,
+    verifier::{proof_from_bytes, verify},
+};
+
+/// Holds a parsed verifying key, so checking many proofs reads the `.vk` once.
+#[wasm_bindgen]
+pub struct VcVerifier {
+    vk: PreparedVerifyingKey<Bn254>,
+}
+
+#[wasm_bindgen]
+impl VcVerifier {
+    /// `vk` is the content of a `.vk` file.
+    #[wasm_bindgen(constructor)]
+    pub fn new(vk: &[u8]) -> Result<VcVerifier, JsError> {
+        let (_, vk) = read_verifying_key(vk)
+            .map_err(|e| JsError::new(&format!("Cannot load verifying key: {:?}", e)))?;
+        Ok(Self { vk })
+    }
+
+    /// `proof` is the compressed proof and `public_input` a `VcVerifyInput` as
+    /// JSON. Returns whether the proof is valid; malformed arguments throw.
+    pub fn verify(&self, proof: &[u8], public_input: &str) -> Result<bool, JsError> {
+        let proof = proof_from_bytes(proof).map_err(|e| JsError::new(&e))?;
+        let public_input: VcVerifyInput = serde_json::from_str(public_input)
+            .map_err(|e| JsError::new(&format!("Invalid public input: {}", e)))?;
+        verify(&self.vk, &proof, &public_input).map_err(|e| JsError::new(&e))
+    }
+}
+
+/// One-shot form of [`VcVerifier::verify`].
+#[wasm_bindgen(js_name = verifyVc)]
+pub fn verify_vc(vk: &[u8], proof: &[u8], public_input: &str) -> Result<bool, JsError> {
+    VcVerifier::new(vk)?.verify(proof, public_input)
+}
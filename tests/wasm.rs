@@ -0,0 +1,27 @@
+//! Run with `wasm-pack test --node -- --no-default-features --features wasm`.
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+
+use vc_prove::wasm::{verify_vc, VcVerifier};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+const VK: &[u8] = include_bytes!("fixtures/verifier.vk");
+const PROOF: &[u8] = include_bytes!("fixtures/verifier.proof");
+const INPUT: &str = include_str!("fixtures/verifier_input.json");
+
+#[wasm_bindgen_test]
+fn verifies_fixture() {
+    let verifier = VcVerifier::new(VK).unwrap();
+    assert!(verifier.verify(PROOF, INPUT).unwrap());
+    assert!(verify_vc(VK, PROOF, INPUT).unwrap());
+
+    let wrong_date = INPUT.replace("20000304", "20000305");
+    assert!(!verifier.verify(PROOF, &wrong_date).unwrap());
+}
+
+#[wasm_bindgen_test]
+fn rejects_malformed_arguments() {
+    let verifier = VcVerifier::new(VK).unwrap();
+    assert!(VcVerifier::new(&VK[1..]).is_err());
+    assert!(verifier.verify(&PROOF[1..], INPUT).is_err());
+    assert!(verifier.verify(PROOF, "{}").is_err());
+}